@@ -1,8 +1,8 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use itertools::Itertools;
 
-use crate::{tprint, utils::read_file20};
+use crate::{graph::DiGraph, tprint, utils::read_file20};
 
 pub type AocRes = Result<u32, String>;
 
@@ -10,71 +10,18 @@ pub fn main() -> (AocRes, AocRes) {
     (part1(), part2())
 }
 
-type BagManifest = HashMap<String, Bag>;
-
 fn part1() -> AocRes {
-    let bag_manifest = _get_data("07.txt");
-    let mut cache = HashMap::new();
-
-    Ok(bag_manifest
-        .keys()
-        .map(|name| contains("shiny gold", name, &bag_manifest, &mut cache))
-        .filter(|v| *v != 0)
-        .count() as u32)
-
-}
-
-fn contains(
-    target: &str,
-    current: &str,
-    bag_manifest: &BagManifest,
-    cache: &mut HashMap<String, u32>,
-) -> u32 {
-    if let Some(amount) = cache.get(current) {
-        return *amount;
-    }
-    let current_bag = bag_manifest.get(current).unwrap();
-    if let Some(target_amount) = current_bag.contents.get(target) {
-        let target_amount = *target_amount;
-        cache.insert(current.to_string(), target_amount);
-        return target_amount;
-    }
-
-    let res = current_bag
-        .contents
-        .iter()
-        .map(|(name, amount)| *amount * contains(target, name, bag_manifest, cache))
-        .filter(|v| *v != 0)
-        .sum();
-    cache.insert(current.to_string(), res);
-    res
+    let rules = _get_data("07.txt");
+    let shiny_gold = rules.id("shiny gold").unwrap();
+    Ok(rules.ancestors(shiny_gold).len() as u32)
 }
 
 fn part2() -> AocRes {
-    let bag_manifest = _get_data("07.txt");
-    let mut cache = HashMap::new();
-    Ok(contains2(bag_manifest.get("shiny gold").unwrap(), 1, &bag_manifest, &mut cache) - 1)
-    // Err("unsolved".to_string())
+    let rules = _get_data("07.txt");
+    let shiny_gold = rules.id("shiny gold").unwrap();
+    Ok(rules.weighted_descendants(shiny_gold) as u32)
 }
 
-fn contains2(
-    bag: &Bag,
-    amount: u32,
-    bag_manifest: &BagManifest,
-    cache: &mut HashMap<String, u32>,
-) -> u32 {
-    if let Some(res) = cache.get(&bag.name) {
-        return *res;
-    }
-    let res = bag.contents.iter().map(|(name, inner_amount)| {
-        let inner_bag = bag_manifest.get(name).unwrap();
-        contains2(inner_bag, *inner_amount, bag_manifest, cache)
-    }).sum::<u32>() + 1u32;
-
-    amount * res
-}
-
-
 #[derive(Debug)]
 struct Bag {
     name: String,
@@ -109,15 +56,16 @@ impl Bag {
         )
     }
 }
-fn _get_data(fname: &str) -> BagManifest {
-    let lines = read_file20(fname);
-    lines
-        .iter()
-        .map(|l| {
-            let bag = Bag::from_str(l);
-            (bag.name.clone(), bag)
-        })
-        .collect()
+/// edges point from a bag to the bags it directly contains, weighted by how many
+fn _get_data(fname: &str) -> DiGraph {
+    let mut rules = DiGraph::new();
+    read_file20(fname).iter().map(Bag::from_str).for_each(|bag| {
+        rules.intern(&bag.name);
+        bag.contents.iter().for_each(|(inner, amount)| {
+            rules.add_edge(&bag.name, inner, *amount as u64);
+        });
+    });
+    rules
 }
 
 #[cfg(test)]
@@ -136,5 +84,14 @@ mod test {
         assert!(bag.contents.is_empty());
     }
 
+    #[test]
+    fn test_example() {
+        let rules = _get_data("07.txt.test");
+        assert_eq!(rules.ancestors(rules.id("shiny gold").unwrap()).len(), 4);
+        assert_eq!(rules.weighted_descendants(rules.id("shiny gold").unwrap()), 32);
+        let rules = _get_data("07.txt.test2");
+        assert_eq!(rules.weighted_descendants(rules.id("shiny gold").unwrap()), 126);
+    }
+
 
 }
@@ -1,8 +1,12 @@
-use std::collections::HashMap;
+use std::str::FromStr;
 
-use itertools::Itertools;
-
-use crate::utils::read_file20;
+use crate::{
+    utils::read_file20,
+    vm::{
+        handheld::{Handheld, ACC},
+        Halt, Machine,
+    },
+};
 
 pub type AocRes = Result<i32, String>;
 
@@ -10,147 +14,59 @@ pub fn main() -> (AocRes, AocRes) {
     (part1(), part2())
 }
 
+/// acc right before any instruction runs twice
 fn part1() -> AocRes {
-    let mut m = _get_data("08.txt");
-    loop {
-        if let Some(res) = m.next() {
-            return Ok(res);
-        }
+    let mut m = _get_data("08.txt")?;
+    m.detect_loops();
+    match m.run() {
+        Halt::Loop(_) => Ok(m.registers[ACC] as i32),
+        halt => Err(format!("expected a loop, got {halt:?}")),
     }
 }
 
+/// swap the one `nop`/`jmp` that lets the program finish
 fn part2() -> AocRes {
-    let m = _get_data("08.txt");
-    let res = (0..m.instructions.len()).find_map(|i| {
-        if m.instructions[i].type_ == InstructionType::Acc {
-            return None;
-        }
-
-        let res: i32;
-        let mut local_m = m.clone();
-        let cur_i = &mut local_m.instructions[i];
-        cur_i.type_ = match cur_i.type_ {
-            InstructionType::Nop => InstructionType::Jmp,
-            InstructionType::Jmp => InstructionType::Nop,
-            InstructionType::Acc => panic!(),
-        };
-
-        loop {
-            if let Some(val) = local_m.next() {
-                res = val;
-                break;
-            }
-        }
-
-        match local_m.called_twice() {
-            true => None,
-            false => Some(res),
-        }
-
-    });
-    Ok(res.unwrap())
-}
-
-fn _get_data(fname: &str) -> Machine{
-    Machine::from_str(read_file20(fname).join("\n"))
-}
-
-#[derive(Debug, Clone)]
-struct Machine {
-    acc: i32,
-    instructions: Vec<Instruction>,
-    inst_pointer: usize,
-    visited: HashMap<usize, u32>,
-}
-
-
-impl Machine {
-    fn from_str(s: impl AsRef<str>) -> Self {
-        let instructions = s.as_ref().split('\n').map(Instruction::from_str).collect_vec();
-        Self {
-            acc: 0,
-            instructions,
-            inst_pointer: 0,
-            visited: hashmap! {},
-        }
-    }
-    fn next(&mut self) -> Option<i32> {
-        *self.visited.entry(self.inst_pointer).or_default() += 1;
-
-        if self.called_twice() || self.inst_pointer >= self.instructions.len() {
-            return Some(self.acc)
-        }
-
-        let inst = &self.instructions[self.inst_pointer];
-        let offset = match inst.type_ {
-            InstructionType::Nop => 1,
-            InstructionType::Acc => {
-                self.acc += inst.offset;
-                1
-            },
-            InstructionType::Jmp => inst.offset,
-        };
-        self.inst_pointer = (self.inst_pointer as i32 + offset) as usize;
-        None
-    }
-
-    fn called_twice(&self) -> bool {
-        match self.visited.get(&self.inst_pointer) {
-            Some(val) => *val > 1,
-            None => false,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-struct Instruction {
-    type_: InstructionType,
-    offset: i32,
-}
-
-impl Instruction {
-    fn from_str(s: impl AsRef<str>) -> Self {
-        let (inst, offset) = s.as_ref().split_once(' ').unwrap();
-        Self {
-            type_: InstructionType::from_str(inst),
-            offset: offset.parse().unwrap(),
-        }
+    let mut m = _get_data("08.txt")?;
+    let addr = m.find_patch().ok_or("no patch terminates")?;
+    m.patch(addr)?;
+    match m.run() {
+        Halt::Terminated => Ok(m.registers[ACC] as i32),
+        halt => Err(format!("patched program didn't terminate: {halt:?}")),
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
-enum InstructionType {
-    Acc,
-    Nop,
-    Jmp,
-}
-
-impl InstructionType {
-    fn from_str(s: impl AsRef<str>) -> Self {
-        match s.as_ref() {
-            "nop" => Self::Nop,
-            "acc" => Self::Acc,
-            "jmp" => Self::Jmp,
-            _ => panic!(),
-        }
-    }
+fn _get_data(fname: &str) -> Result<Machine<Handheld>, String> {
+    Machine::from_str(&read_file20(fname).join("\n"))
 }
 
 #[cfg(test)]
 mod test {
-    use crate::tprint;
+    use crate::vm::handheld::{Instruction, InstructionType};
 
     use super::*;
 
     #[test]
-    fn test_inst_type() { 
-        assert_eq!(InstructionType::from_str("nop"), InstructionType::Nop);
+    fn test_inst_type() {
+        assert_eq!(InstructionType::from_str("nop"), Ok(InstructionType::Nop));
     }
 
     #[test]
-    fn test_inst() { 
-        let inst = Instruction::from_str("jmp -3");
+    fn test_inst() {
+        let inst = Instruction::from_str("jmp -3").unwrap();
         assert_eq!(inst.type_, InstructionType::Jmp);
         assert_eq!(inst.offset, -3);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_example() {
+        let mut m = _get_data("08.txt.test").unwrap();
+        m.detect_loops();
+        assert!(matches!(m.run(), Halt::Loop(_)));
+        assert_eq!(m.registers[ACC], 5);
+
+        let mut m = _get_data("08.txt.test").unwrap();
+        m.patch(m.find_patch().unwrap()).unwrap();
+        assert_eq!(m.run(), Halt::Terminated);
+        assert_eq!(m.registers[ACC], 8);
+    }
+}
@@ -10,6 +10,7 @@ pub mod day09;
 pub mod day10;
 pub mod day11;
 pub mod day11_faster;
+pub mod day12;
 pub mod day13;
 pub mod day14;
 pub mod day15;
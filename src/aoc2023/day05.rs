@@ -5,7 +5,7 @@ use std::str::FromStr;
 use itertools::Itertools;
 use strum_macros::EnumString;
 
-use crate::{print1, utils::read_file23};
+use crate::{interval::IntervalSet, print1, utils::read_file23};
 
 pub fn main() -> (u64, Result<u64, String>) {
     (part1(), part2())
@@ -18,30 +18,13 @@ fn part1() -> u64 {
     _find_lowest_location(&almanac, &t)
 }
 
-/// need totally different approach
-/// idk, map out all ranges and work on them directly vs individual elements of the ranges?
+/// seeds come in `start len` pairs covering billions of seeds, so push whole ranges
+/// through each map instead
 fn part2() -> Result<u64, String> {
-    Err("python".to_string())
-    // let almanac = Almanac::from_fname("05.txt");
-    // let mut seeds: Vec<u64> = Vec::new();
-    // let starts: HashSet<u64> = almanac
-    //     .seeds
-    //     .clone()
-    //     .chunks(2)
-    //     .filter_map(|start_end|
-    //         match start_end {
-    //             [start, end] => Some((*start, *end)),
-    //             _ => None,
-    //         }
-    //     )
-    //     .flat_map(|(start, end)| start..=(start + end))
-    //     .collect();
-    // print1!(starts.len());
-    // // for chunk in almanac.seeds.clone().chunks(2) {
-    // //     print1!(chunk);
-    // // }
-
-    // 0u64
+    let almanac = Almanac::from_fname("05.txt");
+    almanac
+        .lowest_location(&almanac.seed_ranges())
+        .ok_or("no seeds".to_string())
 }
 
 fn _find_lowest_location(almanac: &Almanac, seeds: &HashSet<u64>) -> u64 {
@@ -108,14 +91,40 @@ struct Almanac {
 // =============================================================================
 
 impl Range {
+    /// one past the last value
+    fn end(&self) -> u64 {
+        self.start + self.range
+    }
+
+    fn span(&self) -> std::ops::Range<u64> {
+        self.start..self.end()
+    }
+
+    fn from_set(type_: ResourceType, set: &IntervalSet<u64>) -> Vec<Range> {
+        set.iter()
+            .map(|r| Range {
+                type_,
+                start: *r.start(),
+                range: r.end() - r.start() + 1,
+            })
+            .collect()
+    }
+
+    /// sort and join any ranges that overlap or touch
+    fn merge(ranges: Vec<Range>) -> Vec<Range> {
+        match ranges.first() {
+            Some(first) => Range::from_set(first.type_, &ranges.iter().map(Range::span).collect()),
+            None => vec![],
+        }
+    }
+
     fn calc_offset(&self, resource: &Resource) -> Option<u64> {
         if resource.type_ != self.type_ {
             return None;
         }
-        if !(self.start <= resource.value && resource.value < self.start + self.range) {
-            return None;
-        }
-        Some(resource.value - self.start)
+        IntervalSet::from_range(self.span())
+            .contains(resource.value)
+            .then(|| resource.value - self.start)
     }
 }
 
@@ -156,6 +165,15 @@ impl Pair {
         }
         None
     }
+
+    /// the part of `from` this pair covers, already converted
+    fn convert_set(&self, from: &IntervalSet<u64>) -> IntervalSet<u64> {
+        let shift = |v: u64| self.to.start + (v - self.from.start);
+        from.intersection(&IntervalSet::from_range(self.from.span()))
+            .iter()
+            .map(|r| shift(*r.start())..=shift(*r.end()))
+            .collect()
+    }
 }
 
 impl ResourceMap {
@@ -185,6 +203,36 @@ impl ResourceMap {
         }
     }
 
+    fn convert_ranges(&self, from: Vec<Range>, to: ResourceType) -> Vec<Range> {
+        let Some(first) = from.first() else {
+            return vec![];
+        };
+        let mut res = from.clone();
+        for (_, cur_to) in self.path(first.type_, to) {
+            res = self._convert_ranges_helper(res, cur_to);
+        }
+        res
+    }
+
+    /// every pair takes the bits it covers, anything no pair covers keeps its value
+    fn _convert_ranges_helper(&self, from: Vec<Range>, to: ResourceType) -> Vec<Range> {
+        let Some(first) = from.first() else {
+            return vec![];
+        };
+        let pairs = match self.data.get(&(first.type_, to)) {
+            Some(pairs) => pairs,
+            None => panic!("no conversion possible! {:?} {to:?}", first.type_),
+        };
+
+        let mut res = IntervalSet::new();
+        let mut unmapped: IntervalSet<u64> = from.iter().map(Range::span).collect();
+        for p in pairs {
+            res = res.union(&p.convert_set(&unmapped));
+            unmapped = unmapped.difference(&IntervalSet::from_range(p.from.span()));
+        }
+        Range::from_set(to, &res.union(&unmapped))
+    }
+
     fn path(&self, from: ResourceType, to: ResourceType) -> Vec<(ResourceType, ResourceType)> {
         if from == to {
             return vec![];
@@ -220,6 +268,28 @@ impl Almanac {
             map: resource_map,
         }
     }
+    /// seeds read as `start len` pairs
+    fn seed_ranges(&self) -> Vec<Range> {
+        let ranges = self
+            .seeds
+            .chunks(2)
+            .map(|pair| Range {
+                type_: ResourceType::Seed,
+                start: pair[0],
+                range: pair[1],
+            })
+            .collect();
+        Range::merge(ranges)
+    }
+
+    fn lowest_location(&self, seeds: &[Range]) -> Option<u64> {
+        self.map
+            .convert_ranges(seeds.to_vec(), ResourceType::Location)
+            .iter()
+            .map(|r| r.start)
+            .min()
+    }
+
     fn convert(&self, seed_num: u64, to: ResourceType) -> Resource {
         let seed = Resource {
             type_: ResourceType::Seed,
@@ -249,3 +319,47 @@ fn _parse_types(s: &str) -> (ResourceType, ResourceType) {
         ResourceType::from_str(to).unwrap(),
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let almanac = Almanac::from_fname("05.txt.test");
+        let seeds = HashSet::from_iter(almanac.seeds.clone());
+        assert_eq!(_find_lowest_location(&almanac, &seeds), 35);
+        assert_eq!(almanac.lowest_location(&almanac.seed_ranges()), Some(46));
+    }
+
+    #[test]
+    fn test_ranges_match_values() {
+        let almanac = Almanac::from_fname("05.txt.test");
+        for seed in almanac.seed_ranges() {
+            let mut expected = (seed.start..seed.end())
+                .map(|v| almanac.convert(v, ResourceType::Location).value)
+                .collect_vec();
+            expected.sort();
+            let res = almanac
+                .map
+                .convert_ranges(vec![seed], ResourceType::Location)
+                .iter()
+                .flat_map(|r| r.start..r.end())
+                .collect_vec();
+            assert_eq!(res, expected);
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let r = |start, range| Range {
+            type_: ResourceType::Soil,
+            start,
+            range,
+        };
+        assert_eq!(
+            Range::merge(vec![r(10, 5), r(0, 3), r(3, 2), r(12, 10)]),
+            vec![r(0, 5), r(10, 12)]
+        );
+    }
+}
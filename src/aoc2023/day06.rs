@@ -1,10 +1,9 @@
 use itertools::Itertools;
 
-use crate::{print1, utils::read_file23};
+use crate::{math::quadratic_below_zero, print1, utils::read_file23};
 
 pub fn main() -> (i64, i64) {
-    (part1("06.txt"),
-    part2("06.txt"))
+    (part1("06.txt"), part2("06.txt"))
 }
 
 fn part1(fname: &str) -> i64 {
@@ -34,38 +33,12 @@ fn _calc_total_ways(times: &[i64], distances: &[i64]) -> i64 {
         .product::<i64>()
 }
 
+/// charging for `c` of the `t` ms travels `c * (t - c)`, so the winning charges are where
+/// `c^2 - t*c + d < 0`
 fn _calc_ways(t: i64, d: i64) -> i64 {
-    let (min, max) = _calc_roots(t, d);
-    let offset = _get_ends_offset(t, min, d) + _get_ends_offset(t, max, d);
-    max - min + 1 - offset
+    quadratic_below_zero(1, -t, d).map_or(0, |r| r.end() - r.start() + 1)
 }
 
-fn _get_ends_offset(t_total: i64, t_charge: i64, dist: i64) -> i64 {
-    let v = -(t_charge.pow(2) - t_total * t_charge);
-    match v > dist {
-        true => 0,
-        _ => 1,
-    }
-}
-
-/// distance = (t_total - t_charge) * t_charge
-/// t_charge ** 2 - t_total * t_charge + distance
-fn _calc_roots(t: i64, d: i64) -> (i64, i64) {
-    let (r1, r2) = _calc_roots_float(t, d);
-    (r1.min(r2).ceil() as i64, r1.max(r2).floor() as i64)
-}
-
-fn _calc_roots_float(t: i64, d: i64) -> (f64, f64) {
-    let inner = ((t.pow(2) - 4 * d) as f64).sqrt();
-    let t = t as f64;
-
-    let r1 = (t + inner) / 2.;
-    let r2 = (t - inner) / 2.;
-
-    (r1.min(r2), r1.max(r2))
-}
-
-
 fn _parse_ints(s: &str) -> Vec<i64> {
     s.split(' ').filter_map(|v| v.parse::<i64>().ok()).collect()
 }
@@ -1,12 +1,5 @@
-use cached::cached;
-use once_cell::sync::Lazy;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use itertools::Itertools;
 
-use maplit::hashmap;
-use strum_macros::EnumIter;
-
-use crate::print1;
 use crate::utils::read_file23;
 
 pub type AocRes = Result<u64, String>;
@@ -16,20 +9,18 @@ pub fn main() -> (AocRes, AocRes) {
 }
 
 fn part1() -> AocRes {
-    let mut cards = _get_data("07.txt");
-    cards.sort();
-    let res = cards.iter().zip(1..).map(|(c, m)| c.bid * m).sum::<u64>();
-
-    Ok(res)
+    let rules = Rules::STANDARD;
+    Ok(rules.winnings(&_get_data("07.txt", &rules)?))
 }
 
-fn _get_data(fname: &str) -> Vec<Hand> {
-    let data = read_file23(fname);
-    data.iter().map(Hand::from_str).collect()
+fn part2() -> AocRes {
+    let rules = Rules::JOKERS;
+    Ok(rules.winnings(&_get_data("07.txt", &rules)?))
 }
 
-fn part2() -> AocRes {
-    Err("python".to_string())
+fn _get_data(fname: &str, rules: &Rules) -> Result<Vec<Hand>, String> {
+    let data = read_file23(fname);
+    data.iter().map(|s| Hand::from_str(s, rules)).collect()
 }
 
 #[derive(Debug)]
@@ -38,7 +29,7 @@ struct Hand {
     bid: u64,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum HandType {
     HighCard,
     OnePair,
@@ -49,83 +40,155 @@ enum HandType {
     FiveOfAKind,
 }
 
-impl Hand {
-    fn from_str(s: impl AsRef<str>) -> Self {
-        let (cards, bid) = s.as_ref().split_once(' ').unwrap();
-        Self {
-            cards: cards.to_string(),
-            bid: bid.parse().unwrap(),
-        }
+/// how to rank hands: card strength from weakest to strongest, and optionally a card that
+/// stands in for whatever makes the best hand
+#[derive(Debug, Clone, Copy)]
+struct Rules {
+    order: &'static str,
+    wildcard: Option<char>,
+}
+
+impl Rules {
+    const STANDARD: Rules = Rules {
+        order: "23456789TJQKA",
+        wildcard: None,
+    };
+    /// jacks are jokers: wild, and the weakest card when breaking ties
+    const JOKERS: Rules = Rules {
+        order: "J23456789TQKA",
+        wildcard: Some('J'),
+    };
+
+    /// total of each hand's bid times its rank, weakest hand ranked 1
+    fn winnings(&self, hands: &[Hand]) -> u64 {
+        hands
+            .iter()
+            .sorted_by_cached_key(|h| self.key(h))
+            .zip(1..)
+            .map(|(h, rank)| h.bid * rank)
+            .sum()
     }
-    fn hand_type(&self) -> HandType {
-        match self._count_cards().as_slice() {
-            [5] => HandType::FiveOfAKind,
-            [1, 4] => HandType::FourOfAKind,
-            [2, 3] => HandType::FullHouse,
-            [1, 1, 3] => HandType::ThreeOfAKind,
-            [1, 2, 2] => HandType::TwoPair,
-            [1, 1, 1, 2] => HandType::OnePair,
-            [1, 1, 1, 1, 1] => HandType::HighCard,
-            v => panic!("unexpected hand: {v:?}"),
+
+    /// hand type first, then card by card
+    fn key(&self, hand: &Hand) -> (HandType, Vec<usize>) {
+        (self.hand_type(hand), self.card_ranks(hand))
+    }
+
+    /// wildcards always join the most common other card. more of the same card beats any
+    /// other arrangement, so this is always the best upgrade
+    fn hand_type(&self, hand: &Hand) -> HandType {
+        let wild = hand
+            .cards
+            .chars()
+            .filter(|c| Some(*c) == self.wildcard)
+            .count();
+        let mut counts = hand
+            .cards
+            .chars()
+            .filter(|c| Some(*c) != self.wildcard)
+            .counts()
+            .into_values()
+            .sorted()
+            .rev()
+            .collect_vec();
+        match counts.first_mut() {
+            Some(most) => *most += wild,
+            None => counts.push(wild),
         }
-        // todo!()
+        HandType::from_counts(&counts)
     }
 
-    fn card_ranks(&self) -> Vec<u64> {
-        self.cards
+    /// `hand` must have been parsed with these rules, so every card is in `order`
+    fn card_ranks(&self, hand: &Hand) -> Vec<usize> {
+        hand.cards
             .chars()
-            .map(|c| *CARD_MAP.get(&c).unwrap())
+            .map(|c| self.order.find(c).expect("card checked when parsing"))
             .collect()
     }
+}
 
-    fn _count_cards(&self) -> Vec<i32> {
-        let mut res: HashMap<char, _> = HashMap::new();
-        for c in self.cards.chars() {
-            *res.entry(c).or_insert(0) += 1;
+impl HandType {
+    /// from how many of each card there are, most common first
+    fn from_counts(counts: &[usize]) -> Self {
+        match counts {
+            [5] => HandType::FiveOfAKind,
+            [4, 1] => HandType::FourOfAKind,
+            [3, 2] => HandType::FullHouse,
+            [3, 1, 1] => HandType::ThreeOfAKind,
+            [2, 2, 1] => HandType::TwoPair,
+            [2, 1, 1, 1] => HandType::OnePair,
+            [1, 1, 1, 1, 1] => HandType::HighCard,
+            v => panic!("unexpected hand: {v:?}"),
         }
-        let mut v: Vec<_> = res.values().copied().collect();
-        v.sort();
-        v
     }
 }
 
-static CARD_MAP: Lazy<HashMap<char, u64>> = Lazy::new(|| {
-    let cards = "23456789TJQKA";
-    let mut res: HashMap<_, _> = HashMap::new();
-    cards.chars().zip(0..).for_each(|(c, v)| {
-        res.insert(c, v);
-    });
-    res
-});
-
-impl PartialEq for Hand {
-    fn eq(&self, other: &Self) -> bool {
-        self.cards == other.cards
+impl Hand {
+    /// five cards, each one that `rules` knows how to rank, then the bid
+    fn from_str(s: impl AsRef<str>, rules: &Rules) -> Result<Self, String> {
+        let s = s.as_ref();
+        let (cards, bid) = s.split_once(' ').ok_or(format!("bad hand: {s}"))?;
+        if cards.chars().count() != 5 {
+            return Err(format!("hand needs 5 cards: {s}"));
+        }
+        if let Some(c) = cards.chars().find(|c| !rules.order.contains(*c)) {
+            return Err(format!("unknown card {c}: {s}"));
+        }
+        Ok(Self {
+            cards: cards.to_string(),
+            bid: bid.parse().map_err(|_| format!("bad bid: {s}"))?,
+        })
     }
 }
-impl Eq for Hand {}
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn _hand(cards: &str) -> Hand {
+        Hand {
+            cards: cards.to_string(),
+            bid: 0,
+        }
     }
-}
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match self.hand_type().cmp(&other.hand_type()) {
-            Ordering::Equal => (),
-            other => return other,
+    #[test]
+    fn test_hand_type() {
+        let standard = |cards| Rules::STANDARD.hand_type(&_hand(cards));
+        let jokers = |cards| Rules::JOKERS.hand_type(&_hand(cards));
+        assert_eq!(standard("KTJJT"), HandType::TwoPair);
+        assert_eq!(jokers("KTJJT"), HandType::FourOfAKind);
+        assert_eq!(jokers("JJJJJ"), HandType::FiveOfAKind);
+        assert_eq!(jokers("2345J"), HandType::OnePair);
+        assert_eq!(jokers("22J33"), HandType::FullHouse);
+        // jokers lose ties to everything
+        assert!(Rules::JOKERS.key(&_hand("JKKK2")) < Rules::JOKERS.key(&_hand("TTTT2")));
+    }
+
+    #[test]
+    fn test_example() {
+        let hands = _get_data("07.txt.test", &Rules::STANDARD).unwrap();
+        assert_eq!(Rules::STANDARD.winnings(&hands), 6440);
+        let hands = _get_data("07.txt.test", &Rules::JOKERS).unwrap();
+        assert_eq!(Rules::JOKERS.winnings(&hands), 5905);
+
+        // wild jacks that still rank high win that tie instead
+        let high_jokers = Rules {
+            order: Rules::STANDARD.order,
+            wildcard: Some('J'),
         };
-        for (s, o) in self.card_ranks().iter().zip(other.card_ranks().iter()) {
-            if s == o {
-                continue;
-            }
-            return match s < o {
-                true => Ordering::Less,
-                false => Ordering::Greater,
-            };
-        }
-        Ordering::Equal
+        assert!(high_jokers.key(&_hand("JKKK2")) > high_jokers.key(&_hand("TTTT2")));
+    }
+
+    #[test]
+    fn test_parse() {
+        let rules = Rules::STANDARD;
+        let hand = Hand::from_str("32T3K 765", &rules).unwrap();
+        assert_eq!((hand.cards.as_str(), hand.bid), ("32T3K", 765));
+        assert!(Hand::from_str("32T3X 765", &rules).is_err());
+        assert!(Hand::from_str("32t3K 765", &rules).is_err());
+        assert!(Hand::from_str("32T3 765", &rules).is_err());
+        assert!(Hand::from_str("32T3K", &rules).is_err());
+        assert!(Hand::from_str("32T3K x", &rules).is_err());
     }
 }
@@ -1,11 +1,15 @@
 use std::{collections::HashMap, slice, str::FromStr, vec};
 
 use itertools::Itertools;
-use num_integer::lcm;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-use crate::{print1, utils::read_file23};
+use crate::{
+    dot::{Dot, ToDot},
+    math::lcm_all,
+    print1,
+    utils::read_file23,
+};
 
 pub type AocRes = Result<usize, String>;
 
@@ -68,11 +72,12 @@ impl NodeMap {
 
     // get the lcm of all the paths
     fn part2(&self) -> usize {
-        self.nodes
-            .keys()
-            .filter(|key| key.ends_with('A'))
-            .map(|start| self._traverse(start, false))
-            .fold(1, lcm)
+        lcm_all(
+            self.nodes
+                .keys()
+                .filter(|key| key.ends_with('A'))
+                .map(|start| self._traverse(start, false)),
+        )
     }
 
     fn _traverse(&self, start: &str, is_zzz: bool) -> usize {
@@ -175,7 +180,43 @@ impl<'a> Iterator for DirectionsIter<'a> {
 }
 
 impl Directions {
-    fn iter(&self) -> DirectionsIter {
+    fn iter(&self) -> DirectionsIter<'_> {
         DirectionsIter::new(&self.directions)
     }
 }
+
+// =================================================================================================
+// Dot
+// =================================================================================================
+
+/// start nodes are highlighted, end nodes are boxes
+impl ToDot for NodeMap {
+    fn to_dot(&self) -> Dot {
+        let mut dot = Dot::new("nodes");
+        for name in self.nodes.keys().sorted() {
+            if name.ends_with('A') {
+                dot.node(name, &[("style", "filled"), ("fillcolor", "palegreen")]);
+            } else if name.ends_with('Z') {
+                dot.node(name, &[("shape", "box")]);
+            }
+        }
+        for node in self.nodes.values().sorted_by_key(|n| &n.source) {
+            dot.edge(&node.source, &node.left, Some("L".to_string()));
+            dot.edge(&node.source, &node.right, Some("R".to_string()));
+        }
+        dot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dot() {
+        let dot = _get_data("08.txt.test").to_dot().render();
+        assert!(dot.contains("\"AAA\" -> \"BBB\" [label=\"L\"];"));
+        assert!(dot.contains("\"AAA\" -> \"CCC\" [label=\"R\"];"));
+        assert!(dot.contains("\"ZZZ\" [shape=\"box\"];"));
+    }
+}
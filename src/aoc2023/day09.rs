@@ -1,10 +1,6 @@
-use std::iter::repeat;
+use crate::{math::Differences, utils::read_file23};
 
-use itertools::{unfold, Itertools};
-
-use crate::{print1, utils::read_file23};
-
-pub type AocRes = Result<i32, String>;
+pub type AocRes = Result<i64, String>;
 
 pub fn main() -> (AocRes, AocRes) {
     (part1(), part2())
@@ -12,55 +8,22 @@ pub fn main() -> (AocRes, AocRes) {
 
 fn part1() -> AocRes {
     let data = _get_data("09.txt");
-    Ok(data.iter().map(|d| extrapolate(d, _calc_next)).sum())
+    Ok(data
+        .iter()
+        .map(|d| Differences::new(d.iter().copied()).next() as i64)
+        .sum())
 }
 
 fn part2() -> AocRes {
     let data = _get_data("09.txt");
-    Ok(data.iter().map(|d| extrapolate(d, _calc_prev)).sum())
-}
-
-// extrapolate the next value in the series
-fn extrapolate<F>(nums: &[i32], extrap_fn: F) -> i32
-where
-    F: Fn(Vec<Vec<i32>>) -> i32,
-{
-    let mut all_diffs = vec![Vec::from(nums)];
-    while let Some(diffs) = _get_diffs(all_diffs.last().unwrap()) {
-        all_diffs.push(diffs);
-    }
-    extrap_fn(all_diffs)
-}
-
-fn _calc_next(nums: Vec<Vec<i32>>) -> i32 {
-    nums.iter().map(|v| v.last().unwrap()).sum()
-}
-
-fn _calc_prev(nums: Vec<Vec<i32>>) -> i32 {
-    let signs = repeat(vec![1, -1]).flatten().cycle();
-    nums.iter()
-        .zip(signs)
-        .map(|(nums, sign)| nums.first().unwrap() * sign)
-        .sum()
-}
-
-// get diffs between adjacent numbers
-// return None if all vals are 0 else return the result
-fn _get_diffs(nums: &Vec<i32>) -> Option<Vec<i32>> {
-    let res: Vec<i32> = nums
-        .as_slice()
-        .windows(2)
-        .map(|window| window[1] - window[0])
-        .collect();
-
-    match res.iter().any(|v| *v != 0) {
-        true => Some(res),
-        _ => None,
-    }
+    Ok(data
+        .iter()
+        .map(|d| Differences::new(d.iter().copied()).prev() as i64)
+        .sum())
 }
 
 // parse lines to, e.g. [[1, 2, 3], [4, 5, 6]]
-fn _get_data(fname: &str) -> Vec<Vec<i32>> {
+fn _get_data(fname: &str) -> Vec<Vec<i64>> {
     read_file23(fname)
         .iter()
         .map(|line| {
@@ -70,3 +33,19 @@ fn _get_data(fname: &str) -> Vec<Vec<i32>> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let tables = _get_data("09.txt.test")
+            .into_iter()
+            .map(Differences::new)
+            .collect::<Vec<_>>();
+        assert_eq!(tables.iter().map(Differences::next).sum::<i128>(), 114);
+        assert_eq!(tables.iter().map(Differences::prev).sum::<i128>(), 2);
+        assert_eq!(tables[2].degree(), Some(3));
+    }
+}
@@ -9,7 +9,12 @@ use once_cell::sync::Lazy;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use crate::{print1, tprint, utils::read_file23};
+use crate::{
+    print1,
+    search::{bfs, StateSpace},
+    tprint,
+    utils::read_file23,
+};
 
 type AocRes = Result<u32, &'static str>;
 
@@ -90,20 +95,24 @@ impl System {
     // BFS the path from the start around the loop and calculate the distance
     // to each node
     fn calc_distance(&mut self) {
-        let mut to_check = VecDeque::from(vec![(self.start, 0u32)]);
-        while let Some((p, distance)) = to_check.pop_front() {
-            if let Some(node) = self.graph.get_mut(&p) {
-                if let Some(d) = node.distance {
-                    if d <= distance {
-                        continue;
-                    }
-                }
-                node.distance = Some(distance);
-                node.connections.iter().for_each(|d| {
-                    to_check.push_back((node.point + *d, distance + 1));
-                })
-            }
-        }
+        let res = bfs(self, [self.start]);
+        res.dist.iter().for_each(|(p, distance)| {
+            self.graph.get_mut(p).unwrap().distance = Some(*distance);
+        });
+    }
+}
+
+impl StateSpace for System {
+    type State = Point;
+
+    fn successors(&self, p: &Point) -> Vec<(Point, u32)> {
+        let node = &self.graph[p];
+        node.connections
+            .iter()
+            .map(|d| node.point + *d)
+            .filter(|p| self.graph.contains_key(p))
+            .map(|p| (p, 1))
+            .collect()
     }
 }
 
@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use itertools::Itertools;
 
-use crate::{tprint, utils::read_file23};
+use crate::{compress::compress_xy, tprint, utils::read_file23};
 
 pub type AocRes = Result<i64, String>;
 
@@ -18,7 +18,6 @@ fn part2() -> AocRes {
 
 fn _both(fname: &str, expansion_coefficient: i64) -> AocRes {
     let universe = _get_data(fname, expansion_coefficient);
-    // tprint!(universe.get_all_occupied());
     Ok(universe
         .calc_pairs()
         .iter()
@@ -35,27 +34,12 @@ fn _get_data(fname: &str, expansion_coefficient: i64) -> Universe {
 // STRUCTs/ENUMs
 // =============================================================================
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
-enum Object {
-    Galaxy,
-    Space,
-}
-
 #[derive(Debug)]
 struct Universe {
-    umap: HashMap<Point, Object>,
-    x_expansion: Expansion,
-    y_expansion: Expansion,
+    galaxies: Vec<Point>,
 }
 
-#[derive(Debug)]
-struct Expansion(Vec<i64>);
-
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
-struct Point {
-    x: i64,
-    y: i64,
-}
+type Point = (i64, i64);
 
 // =============================================================================
 // IMPLs
@@ -63,93 +47,57 @@ struct Point {
 
 impl Universe {
     fn calc_distance(&self, p1: Point, p2: Point) -> i64 {
-        (p2.x - p1.x).abs()
-            + (p2.y - p1.y).abs()
-            + self.x_expansion.expand(p1.x, p2.x)
-            + self.y_expansion.expand(p1.y, p2.y)
+        (p2.0 - p1.0).abs() + (p2.1 - p1.1).abs()
     }
 
     fn calc_pairs(&self) -> Vec<(Point, Point)> {
-        let occupied = self.get_all_occupied();
-        let mut res = vec![];
-        for i in 0..occupied.len() {
-            for j in i + 1..occupied.len() {
-                res.push((occupied[i], occupied[j]));
-            }
-        }
-        res
+        self.galaxies.iter().copied().tuple_combinations().collect()
     }
+}
 
-    fn get_all_occupied(&self) -> Vec<Point> {
-        self.umap
+/// constructor
+impl Universe {
+    /// empty rows/cols are exactly the gaps between the compressed galaxy coords,
+    /// so stretching those gaps by the coefficient expands the universe
+    fn from_str(s: impl AsRef<str>, expansion_coefficient: i64) -> Self {
+        let galaxies = s
+            .as_ref()
+            .split('\n')
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.char_indices()
+                    .filter(|(_, c)| *c == '#')
+                    .map(move |(x, _)| (x as i64, y as i64))
+            })
+            .collect_vec();
+
+        let (xs, ys) = compress_xy(&galaxies);
+        let (x_expanded, y_expanded) = (
+            xs.expanded(expansion_coefficient),
+            ys.expanded(expansion_coefficient),
+        );
+        let galaxies = galaxies
             .iter()
-            .filter_map(|(p, obj)| match obj {
-                Object::Galaxy => Some(*p),
-                _ => None,
+            .map(|(x, y)| {
+                (
+                    x_expanded[xs.index_of(*x).unwrap()],
+                    y_expanded[ys.index_of(*y).unwrap()],
+                )
             })
-            .collect()
-    }
-}
+            .collect();
 
-impl Expansion {
-    fn expand(&self, start: i64, end: i64) -> i64 {
-        let (start, end) = (start.min(end) as usize, start.max(end) as usize);
-        self.0[end] - self.0[start]
+        Self { galaxies }
     }
 }
 
-impl Object {
-    fn from_char(c: char) -> Self {
-        match c {
-            '.' => Self::Space,
-            '#' => Self::Galaxy,
-            _ => panic!("couldn't match char {c}"),
-        }
-    }
-}
+#[cfg(test)]
+mod test {
+    use super::*;
 
-/// constructor
-impl Universe {
-    fn from_str(s: impl AsRef<str>, expansion_coefficient: i64) -> Self {
-        let mut umap = HashMap::new();
-        let mut xmax = 0;
-        let mut ymax = 0;
-        let mut occupied_xs: HashSet<i64> = HashSet::new();
-        let mut occupied_ys: HashSet<i64> = HashSet::new();
-
-        s.as_ref().split('\n').enumerate().for_each(|(y, line)| {
-            ymax = y as i64;
-            line.chars().enumerate().for_each(|(x, c)| {
-                let obj = Object::from_char(c);
-                let (x, y) = (x as i64, y as i64);
-                umap.insert(Point { x, y }, obj);
-                if obj == Object::Galaxy {
-                    occupied_xs.insert(x);
-                    occupied_ys.insert(y);
-                }
-                xmax = x;
-            })
-        });
-
-        // get the cumulative sum of the expansion
-        let calculate_expansion = |max, occupied: HashSet<i64>| {
-            let mut cum_sum = 0;
-            let res = (0..=max)
-                .map(|v| {
-                    cum_sum += match occupied.contains(&v) {
-                        true => 0,
-                        false => expansion_coefficient - 1,
-                    };
-                    cum_sum
-                })
-                .collect();
-            Expansion(res)
-        };
-
-        Self {
-            umap,
-            x_expansion: calculate_expansion(xmax, occupied_xs),
-            y_expansion: calculate_expansion(ymax, occupied_ys),
-        }
+    #[test]
+    fn test_example() {
+        assert_eq!(_both("11.txt.test", 2), Ok(374));
+        assert_eq!(_both("11.txt.test", 10), Ok(1030));
+        assert_eq!(_both("11.txt.test", 100), Ok(8410));
     }
 }
@@ -0,0 +1,158 @@
+use itertools::Itertools;
+
+use crate::utils::{read_file23, Memo};
+
+pub type AocRes = Result<u64, String>;
+
+pub fn main() -> (AocRes, AocRes) {
+    (part1(), part2())
+}
+
+fn part1() -> AocRes {
+    Ok(_get_data("12.txt")?.iter().map(Record::arrangements).sum())
+}
+
+fn part2() -> AocRes {
+    Ok(_get_data("12.txt")?
+        .iter()
+        .map(|r| r.unfold(5).arrangements())
+        .sum())
+}
+
+fn _get_data(fname: &str) -> Result<Vec<Record>, String> {
+    read_file23(fname).iter().map(Record::from_str).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Spring {
+    Operational,
+    Damaged,
+    Unknown,
+}
+
+/// one row of springs and the sizes of its contiguous damaged groups, in order
+#[derive(Debug, Clone)]
+struct Record {
+    springs: Vec<Spring>,
+    groups: Vec<usize>,
+}
+
+impl Record {
+    /// how many ways the unknown springs can be filled in to match `groups`
+    fn arrangements(&self) -> u64 {
+        self._count(&mut Memo::new(), 0, 0)
+    }
+
+    /// `copies` of the springs joined by unknowns, and `copies` of the groups
+    fn unfold(&self, copies: usize) -> Self {
+        let springs = vec![self.springs.clone(); copies];
+        Self {
+            springs: Itertools::intersperse(springs.into_iter(), vec![Spring::Unknown])
+                .flatten()
+                .collect(),
+            groups: self.groups.repeat(copies),
+        }
+    }
+
+    /// arrangements of `springs[s..]` matching `groups[g..]`
+    fn _count(&self, memo: &mut Memo<(usize, usize), u64>, s: usize, g: usize) -> u64 {
+        let Some(spring) = self.springs.get(s) else {
+            return (g == self.groups.len()) as u64;
+        };
+        memo.get_or_insert_with((s, g), |memo| {
+            let mut res = 0;
+            if *spring != Spring::Damaged {
+                res += self._count(memo, s + 1, g);
+            }
+            if *spring != Spring::Operational && self._fits(s, g) {
+                // skip the group and the operational spring that has to follow it
+                let next = s + self.groups[g] + 1;
+                res += self._count(memo, next.min(self.springs.len()), g + 1);
+            }
+            res
+        })
+    }
+
+    /// whether group `g` can start at `s`: room for it, nothing operational inside, and not
+    /// followed directly by another damaged spring
+    fn _fits(&self, s: usize, g: usize) -> bool {
+        let Some(&len) = self.groups.get(g) else {
+            return false;
+        };
+        let end = s + len;
+        end <= self.springs.len()
+            && self.springs[s..end]
+                .iter()
+                .all(|sp| *sp != Spring::Operational)
+            && self.springs.get(end) != Some(&Spring::Damaged)
+    }
+
+    fn from_str(s: impl AsRef<str>) -> Result<Self, String> {
+        let s = s.as_ref();
+        let (springs, groups) = s.split_once(' ').ok_or(format!("bad record: {s}"))?;
+        Ok(Self {
+            springs: springs.chars().map(Spring::from_char).try_collect()?,
+            groups: groups
+                .split(',')
+                .map(|g| g.parse().map_err(|_| format!("bad group in {s}")))
+                .try_collect()?,
+        })
+    }
+}
+
+impl Spring {
+    fn from_char(c: char) -> Result<Self, String> {
+        match c {
+            '.' => Ok(Self::Operational),
+            '#' => Ok(Self::Damaged),
+            '?' => Ok(Self::Unknown),
+            _ => Err(format!("unknown spring {c}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let records = _get_data("12.txt.test").unwrap();
+        assert_eq!(
+            records.iter().map(Record::arrangements).collect_vec(),
+            vec![1, 4, 1, 1, 4, 10]
+        );
+        assert_eq!(records.iter().map(Record::arrangements).sum::<u64>(), 21);
+        assert_eq!(
+            records
+                .iter()
+                .map(|r| r.unfold(5).arrangements())
+                .sum::<u64>(),
+            525152
+        );
+    }
+
+    #[test]
+    fn test_unfold() {
+        let r = Record::from_str(".# 1").unwrap().unfold(5);
+        let springs = r
+            .springs
+            .iter()
+            .map(|s| match s {
+                Spring::Operational => '.',
+                Spring::Damaged => '#',
+                Spring::Unknown => '?',
+            })
+            .collect::<String>();
+        assert_eq!(springs, ".#?.#?.#?.#?.#");
+        assert_eq!(r.groups, vec![1; 5]);
+        assert_eq!(
+            Record::from_str("???.### 1,1,3")
+                .unwrap()
+                .unfold(5)
+                .arrangements(),
+            1
+        );
+        assert!(Record::from_str("?x? 1").is_err());
+    }
+}
@@ -1,15 +1,8 @@
-use std::{
-    collections::HashMap,
-    fmt::Display,
-    iter::{once, successors, zip},
-    ops::{Add, Deref, Neg, Sub},
-};
+use std::fmt::Display;
 
-use itertools::{unfold, Itertools};
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
+use itertools::Itertools;
 
-use crate::{tprint, utils::read_file23};
+use crate::{cycle::state_after, grid::Grid, point::Dir, utils::read_file23};
 
 pub type AocRes = Result<i32, String>;
 
@@ -23,71 +16,22 @@ fn part1() -> AocRes {
     Ok(_calc_north_load(&platform))
 }
 
-#[allow(unreachable_code)]
 fn part2() -> AocRes {
-    // added because this is slow as hell
-    return Ok(90176);
-    let mut platform = _get_data("14.txt");
-    let target_num_cycles = 1_000_000_000;
-    let mut vals = vec![];
-    for _ in 0..400 {
-        platform = platform.cycle();
-        vals.push(_calc_north_load(&platform))
-    }
-
-    let (cycle_start_i, range) = _detect_cycle_start_and_len(&vals);
-    tprint!(cycle_start_i);
-    let cycle = &vals[cycle_start_i..=cycle_start_i + range];
-    tprint!(cycle);
-
-    Ok(cycle[(target_num_cycles - cycle_start_i - 1) % range])
+    let platform = _get_data("14.txt");
+    Ok(_calc_north_load(&_spin(platform, 1_000_000_000)))
 }
 
-fn _detect_cycle_start_and_len(vals: &[i32]) -> (usize, usize) {
-    let mut slow = vals.iter().enumerate();
-    let mut fast = vals.iter().step_by(2);
-    slow.next();
-    fast.next();
-
-    // find the cycle
-    let ((i1, first_pattern_val), (i2, _)) = zip(slow, fast)
-        .filter_map(|((i, v1), v2)| {
-            if v1 == v2 {
-                return Some((i, v1));
-            }
-            None
-        })
-        .skip(4)
-        .take(2)
-        .collect_tuple()
-        .unwrap();
-
-
-    // find the actual start of the cycle
-    let range = i2 - i1;
-    let cycle = &vals[i1..=i2];
-    let first_cycle_start = vals.iter().enumerate().find_map(|(i, v)| {
-        if v != first_pattern_val {
-            return None;
-        }
-        if &vals[i..=i + range] != cycle {
-            return None;
-        }
-        Some(i)
-    }).unwrap();
-
-    (first_cycle_start, range)
+/// spin cycles repeat well before a billion, so only run until the first repeated layout
+fn _spin(platform: Platform, num_cycles: usize) -> Platform {
+    state_after(platform, |p| p.clone().cycle(), num_cycles)
 }
 
 fn _calc_north_load(platform: &Platform) -> i32 {
     platform
-        .layout
+        .round
         .iter()
-        .map(|(p, r)| match r {
-            Rock::Round => platform.height as i32 - p.y + 1,
-            Rock::Square => 0,
-            Rock::Empty => panic!(),
-        })
+        .enumerate()
+        .map(|(y, row)| (platform.height - y) as i32 * row.count_ones() as i32)
         .sum()
 }
 
@@ -96,32 +40,23 @@ fn _get_data(fname: &str) -> Platform {
     Platform::from_str(data)
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 enum Rock {
     Round,
     Square,
     Empty,
 }
 
-#[derive(Debug)]
+/// one `u128` per row for each kind of rock, bit `x` set if there's a rock in column `x`
+///
+/// tilting moves every rock in a row (or every column at once, for north/south) with a
+/// couple of shifts and masks, and the whole thing hashes as a handful of integers
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Platform {
-    layout: HashMap<Point, Rock>,
-    height: u32,
-    width: u32,
-}
-
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy, Hash)]
-struct Point {
-    x: i32,
-    y: i32,
-}
-
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, EnumIter)]
-enum Dir {
-    North,
-    East,
-    South,
-    West,
+    width: usize,
+    height: usize,
+    round: Vec<u128>,
+    square: Vec<u128>,
 }
 
 impl Rock {
@@ -135,158 +70,166 @@ impl Rock {
     }
 }
 
-type GroupedType = HashMap<i32, Vec<(Point, Rock)>>;
-impl Point {
-    fn with_new(&self, value: i32, dir: Dir) -> Self {
-        match dir {
-            Dir::North | Dir::South => Point {
-                x: self.x,
-                y: value,
-            },
-            Dir::East | Dir::West => Point {
-                x: value,
-                y: self.y,
-            },
+impl Platform {
+    fn from_str(s: impl AsRef<str>) -> Self {
+        let grid = Grid::from_str(s, Rock::from_char);
+        assert!(grid.width() <= 128, "platform too wide for a u128 row");
+        let mut res = Platform {
+            width: grid.width(),
+            height: grid.height(),
+            round: vec![0; grid.height()],
+            square: vec![0; grid.height()],
+        };
+        for ((x, y), r) in grid.iter() {
+            match r {
+                Rock::Round => res.round[y] |= 1 << x,
+                Rock::Square => res.square[y] |= 1 << x,
+                Rock::Empty => {}
+            }
         }
+        res
     }
 
-    fn get(&self, dir: Dir) -> i32 {
+    /// roll every round rock as far as it goes towards `dir`
+    fn tilt(mut self, dir: Dir) -> Self {
         match dir {
-            Dir::North | Dir::South => self.y,
-            Dir::West | Dir::East => self.x,
+            Dir::North => self._tilt_rows(true),
+            Dir::South => self._tilt_rows(false),
+            Dir::West => self._tilt_cols(true),
+            Dir::East => self._tilt_cols(false),
         }
+        self
     }
-}
-impl Platform {
-    fn from_str(s: impl AsRef<str>) -> Self {
-        let layout: HashMap<Point, Rock> = s
-            .as_ref()
-            .split('\n')
-            .enumerate()
-            .flat_map(|(y, row)| {
-                row.chars().enumerate().map(move |(x, c)| {
-                    (
-                        Point {
-                            x: x as i32,
-                            y: y as i32,
-                        },
-                        Rock::from_char(c),
-                    )
-                })
-            })
-            .filter(|(_, r)| *r != Rock::Empty)
-            .collect();
-
-        let max = layout.keys().fold(Point { x: 0, y: 0 }, |mut acc, p| {
-            acc.x = acc.x.max(p.x);
-            acc.y = acc.y.max(p.y);
-            acc
-        });
 
-        Platform {
-            layout,
-            height: max.y as u32,
-            width: max.x as u32,
-        }
+    fn cycle(self) -> Self {
+        [Dir::North, Dir::West, Dir::South, Dir::East]
+            .into_iter()
+            .fold(self, |p, d| p.tilt(d))
     }
 
-    fn tilt(self, dir: Dir) -> Self {
-        let mut grouped = Self::_group(self.layout, dir);
-        Self::_sort(&mut grouped, dir);
-        Self {
-            layout: Self::_move(&grouped, dir, self.width, self.height),
-            ..self
-        }
+    fn grid(&self) -> Grid<Rock> {
+        let data = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(
+                |(x, y)| match (self.round[y] >> x & 1, self.square[y] >> x & 1) {
+                    (1, _) => Rock::Round,
+                    (_, 1) => Rock::Square,
+                    _ => Rock::Empty,
+                },
+            )
+            .collect();
+        Grid::new(self.width, self.height, data)
     }
 
-    fn cycle(mut self) -> Self {
-        for d in [Dir::North, Dir::West, Dir::South, Dir::East] {
-            self = self.tilt(d);
-            // println!("{}", "=".repeat(40));
-            // tprint!(d);
-            // println!("{}", self);
+    /// move rocks one row at a time, all columns at once, until nothing moves
+    fn _tilt_rows(&mut self, north: bool) {
+        let pairs = match north {
+            true => (1..self.height).map(|y| (y, y - 1)).collect_vec(),
+            false => (1..self.height).rev().map(|y| (y - 1, y)).collect_vec(),
+        };
+        let mut moved = true;
+        while moved {
+            moved = false;
+            for (from, to) in &pairs {
+                let movable = self.round[*from] & !(self.round[*to] | self.square[*to]);
+                if movable != 0 {
+                    self.round[*from] &= !movable;
+                    self.round[*to] |= movable;
+                    moved = true;
+                }
+            }
         }
-        self
     }
 
-    /// group the rocks by the axis of the dir
-    fn _group(layout: HashMap<Point, Rock>, dir: Dir) -> GroupedType {
-        layout
-            .into_iter()
-            .map(|(p, r)| {
-                let axis = match dir {
-                    Dir::North | Dir::South => p.x,
-                    Dir::East | Dir::West => p.y,
+    /// within each row, shift rocks one column at a time until nothing moves
+    fn _tilt_cols(&mut self, west: bool) {
+        let full = match self.width {
+            128 => u128::MAX,
+            w => (1 << w) - 1,
+        };
+        for (round, square) in self.round.iter_mut().zip(&self.square) {
+            loop {
+                let free = !(*round | square) & full;
+                let movable = match west {
+                    true => *round & (free << 1),
+                    false => *round & (free >> 1),
                 };
-                (axis, (p, r))
-            })
-            .fold(HashMap::new(), |mut acc, (axis, (p, r))| {
-                acc.entry(axis).or_default().push((p, r));
-                acc
-            })
-    }
-
-    /// sort the rocks from closest to dir to farthest dir
-    fn _sort(grouped: &mut GroupedType, dir: Dir) {
-        grouped.values_mut().for_each(|axis_vec| {
-            axis_vec.sort_by_key(|(p, _)| match dir {
-                Dir::North => p.y,
-                Dir::East => -p.x,
-                Dir::South => -p.y,
-                Dir::West => p.x,
-            });
-        });
+                if movable == 0 {
+                    break;
+                }
+                *round &= !movable;
+                *round |= match west {
+                    true => movable >> 1,
+                    false => movable << 1,
+                };
+            }
+        }
     }
+}
 
-    /// move rocks toward the dir
-    fn _move(grouped: &GroupedType, dir: Dir, width: u32, height: u32) -> HashMap<Point, Rock> {
-        let (sign, max) = match dir {
-            Dir::North | Dir::West => (1, -1),
-            Dir::South => (-1, (height + 1) as i32),
-            Dir::East => (-1, (width + 1) as i32),
+impl Display for Rock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let c = match self {
+            Rock::Round => 'O',
+            Rock::Square => '#',
+            Rock::Empty => '.',
         };
-        let start = (Point { x: max, y: max }, Rock::Square);
-
-        grouped
-            .values()
-            .flat_map(|v| {
-                v.iter().fold(vec![start], |mut acc, (p2, r2)| {
-                    let (p1, _) = acc.last().unwrap();
-                    let value = match r2 {
-                        Rock::Round => p1.get(dir) + sign,
-                        Rock::Square => p2.get(dir),
-                        Rock::Empty => panic!(),
-                    };
-                    acc.push((p2.with_new(value, dir), *r2));
-                    acc
-                })
-            })
-            .collect()
+        write!(f, "{}", c)
     }
 }
 
 impl Display for Platform {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let layout = (0..=self.height)
-            .map(|y| {
-                let s: String = (0..=self.width)
-                    .map(|x| {
-                        if let Some(r) = self.layout.get(&Point {
-                            x: x as i32,
-                            y: y as i32,
-                        }) {
-                            return match r {
-                                Rock::Round => 'O',
-                                Rock::Square => '#',
-                                Rock::Empty => '.',
-                            };
-                        }
-                        '.'
-                    })
-                    .collect();
-                s
-            })
-            .join("\n");
-        write!(f, "{}\n{}", "-".repeat(40), layout,)
+        write!(f, "{}\n{}", "-".repeat(40), self.grid())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tilt_north() {
+        let platform = _get_data("14.txt.test").tilt(Dir::North);
+        assert_eq!(_calc_north_load(&platform), 136);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut platform = _get_data("14.txt.test");
+        platform = platform.cycle();
+        let expected = ".....#....\n....#...O#\n...OO##...\n.OO#......\n.....OOO#.\n.O#...O#.#\n....O#....\n......OOOO\n#...O###..\n#..OO#....";
+        assert_eq!(platform.grid().to_string(), expected);
+    }
+
+    #[test]
+    fn test_spin() {
+        let platform = _spin(_get_data("14.txt.test"), 1_000_000_000);
+        assert_eq!(_calc_north_load(&platform), 64);
+    }
+
+    #[test]
+    fn test_tilts_match_grid() {
+        // rolling on the grid directly is slow but obviously right
+        let mut platform = _get_data("14.txt.test");
+        for dir in [Dir::North, Dir::East, Dir::West, Dir::South, Dir::East] {
+            let mut grid = platform.grid();
+            grid.view_mut()
+                .oriented(dir)
+                .tilt_north(|r| *r == Rock::Round, |r| *r == Rock::Square);
+            platform = platform.tilt(dir);
+            assert_eq!(platform.grid().to_string(), grid.to_string(), "{dir:?}");
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let empty = Platform {
+            width: 0,
+            height: 0,
+            round: vec![],
+            square: vec![],
+        };
+        assert_eq!(empty.clone().cycle(), empty);
     }
 }
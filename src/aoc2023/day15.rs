@@ -1,13 +1,8 @@
-use std::{
-    collections::HashMap,
-    error::Error,
-    ops::{Range, RangeFrom},
-};
+use std::fmt::Display;
 
 use itertools::Itertools;
-use once_cell::sync::Lazy;
 
-use crate::{tprint, utils::read_file23};
+use crate::utils::read_file23;
 
 pub type AocRes = Result<u32, String>;
 
@@ -20,12 +15,24 @@ fn part1() -> AocRes {
 }
 
 fn part2() -> AocRes {
-    let mut boxes = (1u32..=256).map(LensBox::new).collect_vec();
-    _get_data("15.txt").split(',').for_each(|s| {
-        let cmd = Command::from_str(s);
+    let mut boxes = Hashmap::new();
+    for cmd in _commands(&_get_data("15.txt"))? {
         cmd.execute(&mut boxes);
-    });
-    Ok(boxes.iter().map(LensBox::calc_focusing_power).sum())
+    }
+    Ok(boxes.focusing_power())
+}
+
+/// box contents after every step, in the same format as the puzzle's worked example
+fn _trace(s: &str) -> Result<String, String> {
+    let mut boxes = Hashmap::new();
+    let steps = _commands(s)?
+        .iter()
+        .map(|cmd| {
+            cmd.execute(&mut boxes);
+            format!("After \"{cmd}\":\n{boxes}")
+        })
+        .collect_vec();
+    Ok(steps.join("\n"))
 }
 
 fn _hash(s: impl AsRef<str>) -> u32 {
@@ -36,6 +43,90 @@ fn _hash(s: impl AsRef<str>) -> u32 {
     })
 }
 
+fn _commands(s: &str) -> Result<Vec<Command>, String> {
+    s.split(',').map(Command::from_str).collect()
+}
+
+/// Holiday ASCII String Helper Manual Arrangement Procedure
+///
+/// 256 boxes, each holding labeled values in the order they were first inserted.
+/// a label always lives in the box its `_hash` points to
+#[derive(Debug, Clone)]
+struct Hashmap<V> {
+    boxes: Vec<Vec<(String, V)>>,
+}
+
+impl<V> Hashmap<V> {
+    fn new() -> Self {
+        Self {
+            boxes: (0..256).map(|_| vec![]).collect(),
+        }
+    }
+
+    /// replace the value in place if `label` is already here, otherwise add it to the back
+    /// of its box. returns the old value
+    fn insert(&mut self, label: &str, value: V) -> Option<V> {
+        let b = &mut self.boxes[_hash(label) as usize];
+        match b.iter_mut().find(|(l, _)| l == label) {
+            Some((_, v)) => Some(std::mem::replace(v, value)),
+            None => {
+                b.push((label.to_string(), value));
+                None
+            }
+        }
+    }
+
+    /// take `label` out, shifting everything behind it forward
+    fn remove(&mut self, label: &str) -> Option<V> {
+        let b = &mut self.boxes[_hash(label) as usize];
+        let pos = b.iter().position(|(l, _)| l == label)?;
+        Some(b.remove(pos).1)
+    }
+
+    fn get(&self, label: &str) -> Option<&V> {
+        self.boxes[_hash(label) as usize]
+            .iter()
+            .find_map(|(l, v)| (l == label).then_some(v))
+    }
+
+    fn len(&self) -> usize {
+        self.boxes.iter().map(Vec::len).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.boxes.iter().all(Vec::is_empty)
+    }
+
+    /// `(box, slot, label, value)` by box then slot, all 0-based
+    fn iter(&self) -> impl Iterator<Item = (usize, usize, &str, &V)> {
+        self.boxes.iter().enumerate().flat_map(|(b, contents)| {
+            contents
+                .iter()
+                .enumerate()
+                .map(move |(slot, (l, v))| (b, slot, l.as_str(), v))
+        })
+    }
+}
+
+impl Hashmap<u32> {
+    fn focusing_power(&self) -> u32 {
+        self.iter()
+            .map(|(b, slot, _, focal_length)| (b as u32 + 1) * (slot as u32 + 1) * focal_length)
+            .sum()
+    }
+}
+
+/// non-empty boxes only, e.g. `Box 3: [ot 9] [ab 5]`
+impl<V: Display> Display for Hashmap<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, b) in self.boxes.iter().enumerate().filter(|(_, b)| !b.is_empty()) {
+            let contents = b.iter().map(|(l, v)| format!("[{l} {v}]")).join(" ");
+            writeln!(f, "Box {i}: {contents}")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 enum Op {
     Add,
@@ -59,7 +150,6 @@ impl Op {
     }
 }
 
-
 #[derive(Debug)]
 struct Command {
     label: String,
@@ -68,78 +158,142 @@ struct Command {
 }
 
 impl Command {
-    fn execute(&self, boxes: &mut [LensBox]) {
-        let b = &mut boxes[_hash(&self.label) as usize];
-        b.apply(self);
+    fn execute(&self, boxes: &mut Hashmap<u32>) {
+        match (self.op, self.focal_length) {
+            (Op::Add, Some(focal_length)) => {
+                boxes.insert(&self.label, focal_length);
+            }
+            _ => {
+                boxes.remove(&self.label);
+            }
+        }
     }
 
-    fn from_str(s: impl AsRef<str>) -> Self {
+    fn from_str(s: impl AsRef<str>) -> Result<Self, String> {
         let s = s.as_ref();
-        let op = s.chars().find_map(Op::from_char).unwrap();
+        let op = s
+            .chars()
+            .find_map(Op::from_char)
+            .ok_or(format!("no op in {s}"))?;
         let (label, focal_length) = s.split_once(op.as_char()).unwrap();
+        let focal_length = match op {
+            Op::Add => Some(
+                focal_length
+                    .parse()
+                    .map_err(|_| format!("bad focal length in {s}"))?,
+            ),
+            Op::Remove => None,
+        };
 
-        Self {
+        Ok(Self {
             label: label.to_string(),
             op,
-            focal_length: focal_length.parse().ok(),
+            focal_length,
+        })
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.label, self.op.as_char())?;
+        if let Some(focal_length) = self.focal_length {
+            write!(f, "{focal_length}")?;
         }
+        Ok(())
     }
 }
 
-#[derive(Debug)]
-struct LensBox {
-    num: u32, // one indexed
-    contents: HashMap<String, u32>,
-    label_order: HashMap<String, u32>,
-    count: RangeFrom<u32>,
+fn _get_data(fname: &str) -> String {
+    read_file23(fname).join("\n")
 }
 
-impl LensBox {
-    fn new(num: u32) -> Self {
-        Self {
-            num,
-            contents: hashmap! {},
-            label_order: hashmap! {},
-            count: 0u32..,
-        }
-    }
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    fn calc_focusing_power(&self) -> u32 {
-        let mut labels = self.label_order.iter().map(|(k, v)| (*v, k)).collect_vec();
-        labels.sort();
-        labels
-            .iter()
-            .map(|(_, label)| *label)
-            .enumerate()
-            .map(|(i, label)| {
-                let i = i as u32;
-                let focal_length = self.contents.get(label).unwrap();
-                self.num * (i + 1) * focal_length
-            })
-            .sum()
+    #[test]
+    fn test_hash() {
+        assert_eq!(_hash("HASH"), 52);
+        assert_eq!(
+            _get_data("15.txt.test").split(',').map(_hash).sum::<u32>(),
+            1320
+        );
     }
 
-    fn apply(&mut self, cmd: &Command) {
-        match cmd.op {
-            // todo: better way to deal with avoiding unnecessary clones
-            Op::Add => {
-                self.contents.insert(cmd.label.clone(), cmd.focal_length.unwrap());
-                if self.label_order.get(&cmd.label).is_none() {
-                    let num = self._next_num();
-                    self.label_order.insert(cmd.label.clone(), num);
-                }
-            }
-            Op::Remove => {
-                self.label_order.remove(&cmd.label);
-                self.contents.remove(&cmd.label);
-            },
-        };
+    #[test]
+    fn test_hashmap() {
+        let mut boxes = Hashmap::new();
+        assert!(boxes.is_empty());
+        assert_eq!(boxes.insert("rn", 1), None);
+        assert_eq!(boxes.insert("cm", 2), None);
+        assert_eq!(boxes.insert("rn", 5), Some(1));
+        assert_eq!(boxes.get("rn"), Some(&5));
+        assert_eq!(boxes.len(), 2);
+        // replacing keeps the slot
+        assert_eq!(
+            boxes
+                .iter()
+                .map(|(b, slot, l, _)| (b, slot, l))
+                .collect_vec(),
+            vec![(0, 0, "rn"), (0, 1, "cm")]
+        );
+        assert_eq!(boxes.remove("rn"), Some(5));
+        assert_eq!(boxes.remove("rn"), None);
+        assert_eq!(boxes.iter().next(), Some((0, 0, "cm", &2)));
+        assert!(Command::from_str("ab=").is_err());
     }
 
-    fn _next_num(&mut self) -> u32 {
-        self.count.next().unwrap()
+    #[test]
+    fn test_trace() {
+        let s = _get_data("15.txt.test");
+        let expected = r#"After "rn=1":
+Box 0: [rn 1]
+
+After "cm-":
+Box 0: [rn 1]
+
+After "qp=3":
+Box 0: [rn 1]
+Box 1: [qp 3]
+
+After "cm=2":
+Box 0: [rn 1] [cm 2]
+Box 1: [qp 3]
+
+After "qp-":
+Box 0: [rn 1] [cm 2]
+
+After "pc=4":
+Box 0: [rn 1] [cm 2]
+Box 3: [pc 4]
+
+After "ot=9":
+Box 0: [rn 1] [cm 2]
+Box 3: [pc 4] [ot 9]
+
+After "ab=5":
+Box 0: [rn 1] [cm 2]
+Box 3: [pc 4] [ot 9] [ab 5]
+
+After "pc-":
+Box 0: [rn 1] [cm 2]
+Box 3: [ot 9] [ab 5]
+
+After "pc=6":
+Box 0: [rn 1] [cm 2]
+Box 3: [ot 9] [ab 5] [pc 6]
+
+After "ot=7":
+Box 0: [rn 1] [cm 2]
+Box 3: [ot 7] [ab 5] [pc 6]
+"#;
+        assert_eq!(_trace(&s).unwrap(), expected);
+
+        let mut boxes = Hashmap::new();
+        _commands(&s)
+            .unwrap()
+            .iter()
+            .for_each(|c| c.execute(&mut boxes));
+        assert_eq!(boxes.focusing_power(), 145);
     }
 }
-fn _get_data(fname: &str) -> String {
-    read_file23(fname).join("\n")
-}
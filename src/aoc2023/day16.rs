@@ -1,17 +1,12 @@
-use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    iter::{repeat, zip},
-    ops::{Add, Neg, Sub},
-};
+use std::collections::{HashSet, VecDeque};
 
-use glam::IVec2;
 use itertools::Itertools;
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
+use rayon::prelude::*;
 
 use crate::{
+    graph::strongly_connected,
+    grid::Grid,
     point::{Dir, Point},
-    tprint,
     utils::read_file23,
 };
 
@@ -23,42 +18,20 @@ pub fn main() -> (AocRes, AocRes) {
 
 fn part1() -> AocRes {
     let cave = _get_data("16.txt");
-    Ok(cave.count_energized(Beam {
+    Ok(cave.beams().count_energized(Beam {
         dir: Dir::East,
         location: Point::new(0, 0),
     }))
 }
 
-#[allow(unreachable_code)]
 fn part2() -> AocRes {
-    // below is really slow
-    return Ok(7572);
     let cave = _get_data("16.txt");
-    let max_xy = cave.layout.keys().fold(Point::new(0, 0), |acc, p| {
-        Point::new(acc.x.max(p.x), acc.y.max(p.y))
-    });
-    let iter_x = || 0..=max_xy.x;
-    let iter_y = || 0..=max_xy.y;
-
-    let east_side = (Dir::West, zip(repeat(max_xy.x), iter_y()).collect_vec());
-    let west_side = (Dir::East, zip(repeat(0), iter_y()).collect_vec());
-    let north_side = (Dir::South, zip(iter_x(), repeat(0)).collect_vec());
-    let south_side = (Dir::North, zip(iter_x(), repeat(max_xy.y)).collect_vec());
-
-    let sources = [east_side, west_side, north_side, south_side];
-    let res = sources
-        .iter()
-        .flat_map(|(dir, point_tuples)| {
-            point_tuples.iter().map(|(x, y)| {
-                cave.count_energized(Beam {
-                    dir: *dir,
-                    location: Point::new(*x, *y),
-                })
-            })
-        })
+    let beams = cave.beams();
+    cave.edge_beams()
+        .par_iter()
+        .map(|beam| beams.count_energized(*beam))
         .max()
-        .unwrap();
-    Ok(res)
+        .ok_or("empty cave".to_string())
 }
 
 fn _get_data(fname: &str) -> Cave {
@@ -68,11 +41,10 @@ fn _get_data(fname: &str) -> Cave {
 // =============================================================================
 // STRUCTS/ENUMS
 // =============================================================================
-type Layout = HashMap<Point, OpticalDevice>;
 
 #[derive(Debug)]
 struct Cave {
-    layout: Layout,
+    layout: Grid<OpticalDevice>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -90,6 +62,38 @@ struct Beam {
     location: Point,
 }
 
+/// a device and the direction a beam leaves it in
+type NodeId = usize;
+
+/// the cells a beam passes through in a straight line, ending on the next device it hits
+/// (or the edge of the cave), along with where it goes from there
+#[derive(Debug, Default)]
+struct Segment {
+    cells: Vec<usize>,
+    next: Vec<NodeId>,
+}
+
+/// precomputed beam propagation for a `Cave`
+///
+/// every (device, outgoing dir) pair is a node whose segment runs to the next device.
+/// beams that loop back on themselves form strongly connected components, and every node in
+/// one energizes exactly the same cells, so the energized set is built once per component
+/// and shared by every entry point that merges into it
+#[derive(Debug)]
+struct Beams<'a> {
+    cave: &'a Cave,
+    /// cell index -> index of the device there
+    device_at: Vec<Option<usize>>,
+    /// node -> its component
+    component: Vec<usize>,
+    /// component -> every cell energized from there on
+    energized: Vec<Bits>,
+}
+
+/// fixed-size bitset of cell indexes
+#[derive(Debug, Clone)]
+struct Bits(Vec<u64>);
+
 // =============================================================================
 // IMPLs
 // =============================================================================
@@ -97,11 +101,40 @@ struct Beam {
 impl Cave {
     fn from_str(s: impl AsRef<str>) -> Self {
         Self {
-            layout: Self::_parse_layout(s.as_ref()),
+            layout: Grid::from_str(s, OpticalDevice::from_char),
         }
     }
 
-    fn count_energized(&self, beam: Beam) -> i32 {
+    fn beams(&self) -> Beams<'_> {
+        Beams::new(self)
+    }
+
+    /// every beam that can enter from outside, pointing inwards
+    fn edge_beams(&self) -> Vec<Beam> {
+        let (w, h) = (self.layout.width() as i32, self.layout.height() as i32);
+        let beam = |x, y, dir| Beam {
+            dir,
+            location: Point::new(x, y),
+        };
+        (0..h)
+            .flat_map(|y| [beam(0, y, Dir::East), beam(w - 1, y, Dir::West)])
+            .chain((0..w).flat_map(|x| [beam(x, 0, Dir::South), beam(x, h - 1, Dir::North)]))
+            .collect()
+    }
+
+    fn get(&self, p: Point) -> Option<&OpticalDevice> {
+        if p.x < 0 || p.y < 0 {
+            return None;
+        }
+        self.layout.get(p.x as usize, p.y as usize)
+    }
+
+    fn _cell(&self, p: Point) -> usize {
+        p.y as usize * self.layout.width() + p.x as usize
+    }
+
+    /// one beam at a time, no precomputation. kept to check `Beams` against
+    fn _count_energized_naive(&self, beam: Beam) -> i32 {
         let mut queue = VecDeque::from([beam]);
         let mut seen = HashSet::<Beam>::new();
         while let Some(beam) = queue.pop_front() {
@@ -109,7 +142,7 @@ impl Cave {
                 continue;
             }
 
-            if let Some(od) = self.layout.get(&beam.location) {
+            if let Some(od) = self.get(beam.location) {
                 seen.insert(beam);
                 queue.extend(od.beams(&beam));
             }
@@ -117,16 +150,111 @@ impl Cave {
         let unique_points: HashSet<Point> = seen.iter().map(|b| b.location).collect();
         unique_points.len() as i32
     }
+}
 
-    fn _parse_layout(s: &str) -> Layout {
-        s.split('\n')
-            .enumerate()
-            .flat_map(|(y, line)| {
-                line.chars().enumerate().map(move |(x, c)| {
-                    (Point::new(x as i32, y as i32), OpticalDevice::from_char(c))
-                })
-            })
-            .collect()
+impl<'a> Beams<'a> {
+    fn new(cave: &'a Cave) -> Self {
+        let devices = cave
+            .layout
+            .iter()
+            .filter(|(_, od)| **od != OpticalDevice::Empty)
+            .map(|((x, y), _)| Point::new(x as i32, y as i32))
+            .collect_vec();
+        let mut device_at = vec![None; cave.layout.width() * cave.layout.height()];
+        for (i, p) in devices.iter().enumerate() {
+            device_at[cave._cell(*p)] = Some(i);
+        }
+        let mut res = Self {
+            cave,
+            device_at,
+            component: vec![],
+            energized: vec![],
+        };
+
+        let segments = devices
+            .iter()
+            .flat_map(|p| _DIRS.map(|dir| res._segment(*p + dir, dir)))
+            .collect_vec();
+        let adj = segments.iter().map(|s| s.next.clone()).collect_vec();
+
+        res.component = vec![0; segments.len()];
+        let num_cells = res.device_at.len();
+        for (c, nodes) in strongly_connected(&adj).iter().enumerate() {
+            let mut bits = Bits::new(num_cells);
+            for n in nodes {
+                res.component[*n] = c;
+                bits.extend(&segments[*n].cells);
+            }
+            // components that can be reached from here have already been built
+            for next in nodes.iter().flat_map(|n| &segments[*n].next) {
+                let next = res.component[*next];
+                if next != c {
+                    bits.union(&res.energized[next]);
+                }
+            }
+            res.energized.push(bits);
+        }
+        res
+    }
+
+    fn count_energized(&self, beam: Beam) -> i32 {
+        let entry = self._segment(beam.location, beam.dir);
+        let mut bits = Bits::new(self.device_at.len());
+        bits.extend(&entry.cells);
+        entry
+            .next
+            .iter()
+            .map(|n| self.component[*n])
+            .unique()
+            .for_each(|c| bits.union(&self.energized[c]));
+        bits.count() as i32
+    }
+
+    fn _node(&self, device: usize, dir: Dir) -> NodeId {
+        device * 4 + dir as usize
+    }
+
+    /// travel from `start` (inclusive) in `dir` until a device or the edge
+    fn _segment(&self, start: Point, dir: Dir) -> Segment {
+        let mut res = Segment::default();
+        let mut cur = start;
+        while let Some(od) = self.cave.get(cur) {
+            let cell = self.cave._cell(cur);
+            res.cells.push(cell);
+            if let Some(device) = self.device_at[cell] {
+                res.next = od
+                    ._new_dirs(dir)
+                    .iter()
+                    .map(|d| self._node(device, *d))
+                    .collect();
+                break;
+            }
+            cur = cur + dir;
+        }
+        res
+    }
+}
+
+/// in the same order as `Dir`'s discriminants, so `dir as usize` indexes it
+const _DIRS: [Dir; 4] = [Dir::North, Dir::East, Dir::South, Dir::West];
+
+impl Bits {
+    fn new(size: usize) -> Self {
+        Self(vec![0; size.div_ceil(64)])
+    }
+
+    fn extend(&mut self, idxs: &[usize]) {
+        for i in idxs {
+            self.0[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    fn union(&mut self, other: &Bits) {
+        self.0.iter_mut().zip(&other.0).for_each(|(a, b)| *a |= b);
+    }
+
+    fn count(&self) -> u32 {
+        self.0.iter().map(|w| w.count_ones()).sum()
     }
 }
 
@@ -177,3 +305,39 @@ impl OpticalDevice {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let cave = _get_data("16.txt.test");
+        let beams = cave.beams();
+        let start = Beam {
+            dir: Dir::East,
+            location: Point::new(0, 0),
+        };
+        assert_eq!(beams.count_energized(start), 46);
+        let best = cave
+            .edge_beams()
+            .iter()
+            .map(|b| beams.count_energized(*b))
+            .max();
+        assert_eq!(best, Some(51));
+    }
+
+    #[test]
+    fn test_matches_naive() {
+        let cave = _get_data("16.txt.test");
+        let beams = cave.beams();
+        assert_eq!(cave.edge_beams().len(), 40);
+        for beam in cave.edge_beams() {
+            assert_eq!(
+                beams.count_energized(beam),
+                cave._count_energized_naive(beam),
+                "{beam:?}"
+            );
+        }
+    }
+}
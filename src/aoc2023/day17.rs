@@ -1,11 +1,9 @@
-use std::collections::{HashMap, VecDeque};
-
 use itertools::Itertools;
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
 
 use crate::{
+    grid::Grid,
     point::{Dir, Point},
+    search::{astar, StateSpace},
     tprint,
     utils::read_file23,
 };
@@ -16,18 +14,16 @@ pub fn main() -> (AocRes, AocRes) {
     (part1(), part2())
 }
 
-#[allow(unreachable_code)]
 fn part1() -> AocRes {
-    return Err("unsolved".to_string());
-    // too slow, need to fix, see obsidian day17
-
-    let mut city = _get_data("17.txt");
-    city.wend();
-    Ok(city.total_heat_loss())
+    let city = _get_data("17.txt");
+    city.min_heat_loss(Rules::CRUCIBLE)
+        .ok_or("no path".to_string())
 }
 
 fn part2() -> AocRes {
-    Err("unsolved".to_string())
+    let city = _get_data("17.txt");
+    city.min_heat_loss(Rules::ULTRA_CRUCIBLE)
+        .ok_or("no path".to_string())
 }
 
 fn _get_data(fname: &str) -> City {
@@ -39,148 +35,117 @@ fn _get_data(fname: &str) -> City {
 // =============================================================================
 #[derive(Debug)]
 struct City {
-    grid: Vec<Vec<Node>>,
+    heat_loss: Grid<u32>,
 }
 
-/// RowColumn
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
-struct RC(i32, i32);
-
+/// a search state: where the crucible is and whether it got there moving along a row or a
+/// column
+///
+/// every move is a turn followed by a whole straight run, so there's no streak to track and
+/// wherever a move ends is somewhere the crucible may stop
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 struct CrucibleInfo {
     location: Point,
-    dir: Dir,
-    streak: u32,
-    heat_loss: u32,
+    horizontal: bool,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, EnumIter)]
-enum Turn {
-    Straight,
-    Right,
-    Left,
+/// how far a crucible must/may go in a straight line before turning or stopping
+#[derive(Debug, Clone, Copy)]
+struct Rules {
+    min_straight: u32,
+    max_straight: u32,
 }
 
-#[derive(Debug)]
-struct Node {
-    visitors: HashMap<CIKey, u32>,
-    heat_loss: u32,
+/// a city searched under a specific set of crucible rules
+struct Journey<'a> {
+    city: &'a City,
+    rules: Rules,
 }
 
-#[derive(Debug, Hash, Eq, PartialEq)]
-struct CIKey(Dir);
-
 // =============================================================================
 // IMPLs
 // =============================================================================
 
-impl CrucibleInfo {
-    // generate next possible crucible infos from existing
-    fn next_infos(&self) -> Vec<Self> {
-        Turn::iter()
-            .filter_map(|t| self._with_new_dir(t))
-            .collect()
-    }
+impl Rules {
+    const CRUCIBLE: Self = Self {
+        min_straight: 1,
+        max_straight: 3,
+    };
+    const ULTRA_CRUCIBLE: Self = Self {
+        min_straight: 4,
+        max_straight: 10,
+    };
+}
 
-    fn key(&self) -> CIKey {
-        CIKey(self.dir)
-    }
+impl StateSpace for Journey<'_> {
+    type State = CrucibleInfo;
 
-    fn _with_new_dir(&self, turn: Turn) -> Option<Self> {
-        let dir = self.dir.turn(turn);
-        let streak = if dir == self.dir {
-            if self.streak == 3 {
-                return None;
-            }
-            self.streak + 1
-        } else {
-            1
+    /// turn onto the other axis and go anywhere from `min_straight` to `max_straight` blocks
+    fn successors(&self, state: &CrucibleInfo) -> Vec<(CrucibleInfo, u32)> {
+        let dirs = match state.horizontal {
+            true => [Dir::North, Dir::South],
+            false => [Dir::East, Dir::West],
         };
+        let mut res = vec![];
+        for dir in dirs {
+            let (mut location, mut loss) = (state.location, 0);
+            for steps in 1..=self.rules.max_straight {
+                location = location + dir;
+                let Some(l) = self.city.get(&location) else {
+                    break;
+                };
+                loss += l;
+                if steps >= self.rules.min_straight {
+                    let next = CrucibleInfo {
+                        location,
+                        horizontal: !state.horizontal,
+                    };
+                    res.push((next, loss));
+                }
+            }
+        }
+        res
+    }
 
-        Some(Self {
-            location: self.location + dir,
-            dir,
-            streak,
-            heat_loss: self.heat_loss,
-        })
+    fn is_goal(&self, state: &CrucibleInfo) -> bool {
+        state.location == self.city.destination()
     }
-}
 
-impl Node {
-    fn update(&mut self, mut ci: CrucibleInfo) -> Option<CrucibleInfo>{
-        ci.heat_loss += self.heat_loss;
-        let loss = self.visitors.entry(ci.key()).or_insert(ci.heat_loss);
-        if *loss < ci.heat_loss {
-            None
-        } else {
-            *loss = ci.heat_loss;
-            Some(ci)
-        }
+    /// manhattan distance, every block loses at least 1
+    fn heuristic(&self, state: &CrucibleInfo) -> u32 {
+        let d = self.city.destination() - state.location;
+        (d.x.abs() + d.y.abs()) as u32
     }
 }
 
 impl City {
-    fn wend(&mut self) {
-        let p = Point::new(0, 0);
-        let start = CrucibleInfo {
-            location: p,
-            dir: Dir::East,
-            heat_loss: 0u32,
-            streak: 0u32,
-        };
-        let mut queue = VecDeque::from([start]);
-        while let Some(ci) = queue.pop_front() {
-            for next_ci in ci.next_infos() {
-                if let Some(node) = self.get(&next_ci.location) {
-                    if let Some(next_ci) = node.update(next_ci) {
-                        queue.push_back(next_ci);
-                    };
-                };
-            }
-        }
+    fn min_heat_loss(&self, rules: Rules) -> Option<u32> {
+        // either way is a turn from the start, so the first run can go east or south
+        let starts = [true, false].map(|horizontal| CrucibleInfo {
+            location: Point::new(0, 0),
+            horizontal,
+        });
+        astar(&Journey { city: self, rules }, starts).goal_cost()
     }
 
-    fn total_heat_loss(&self) -> u32 {
-        let node = self.grid.iter().last().unwrap().iter().last().unwrap();
-        *node.visitors.values().min().unwrap()
+    fn destination(&self) -> Point {
+        Point::new(
+            self.heat_loss.width() as i32 - 1,
+            self.heat_loss.height() as i32 - 1,
+        )
     }
 
     fn from_str(s: impl AsRef<str>) -> Self {
-        let grid = s
-            .as_ref()
-            .split('\n')
-            .map(|line| line.chars().map(|c| {
-                Node {
-                    visitors: HashMap::default(),
-                    heat_loss: c.to_digit(10).unwrap(),
-                }
-            }).collect_vec())
-            .collect_vec();
-
-        Self { grid }
+        Self {
+            heat_loss: Grid::from_str(s, |c| c.to_digit(10).unwrap()),
+        }
     }
 
-    fn get(&mut self, p: &Point) -> Option<&mut Node> {
-        let (row, col) = (p.y, p.x);
-        if row < 0 || col < 0 {
+    fn get(&self, p: &Point) -> Option<u32> {
+        if p.x < 0 || p.y < 0 {
             return None;
         }
-        if let Some(row_vec) = self.grid.get_mut(row as usize) {
-            if let Some(v) = row_vec.get_mut(col as usize) {
-                return Some(v);
-            }
-        }
-        None
-    }
-}
-
-impl Dir {
-    fn turn(&self, turn: Turn) -> Dir {
-        self.rotate(match turn {
-            Turn::Straight => 0,
-            Turn::Right => 1,
-            Turn::Left => -1,
-        })
+        self.heat_loss.get(p.x as usize, p.y as usize).copied()
     }
 }
 
@@ -190,10 +155,17 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_turn() {
-        assert_eq!(Dir::North.turn(Turn::Right), Dir::East);
-        assert_eq!(Dir::North.turn(Turn::Left), Dir::West);
-        assert_eq!(Dir::North.turn(Turn::Straight), Dir::North);
+    fn test_example() {
+        let city = _get_data("17.txt.test");
+        assert_eq!(city.min_heat_loss(Rules::CRUCIBLE), Some(102));
+        assert_eq!(city.min_heat_loss(Rules::ULTRA_CRUCIBLE), Some(94));
+    }
+
+    #[test]
+    fn test_ultra_must_stop_straight() {
+        let city =
+            City::from_str("111111111111\n999999999991\n999999999991\n999999999991\n999999999991");
+        assert_eq!(city.min_heat_loss(Rules::ULTRA_CRUCIBLE), Some(71));
     }
 
     #[test]
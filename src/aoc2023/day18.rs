@@ -1,13 +1,15 @@
 use std::{
     collections::HashSet,
     fmt::{Debug, Display},
-    iter::repeat,
+    iter::repeat_n,
 };
 
-use itertools::{chain, Itertools};
+use itertools::Itertools;
 use strum::IntoEnumIterator;
 
 use crate::{
+    flood::{label_regions, Connectivity},
+    grid::Grid,
     point::{Dir, Point},
     tprint,
     utils::read_file23,
@@ -70,64 +72,29 @@ impl DigPlan {
         self._normalize();
     }
 
+    /// any region of undug ground that doesn't reach the edge is inside the trench
+    ///
+    /// the trench is 4-connected, so the ground around it has to be 8-connected: outside
+    /// ground can slip between two trench cells that only touch at a corner
     fn excavate(&mut self) {
         let max = self.max_point.unwrap();
-        let min = self.min_point.unwrap();
-        let mut all_points = (min.x..=max.x)
-            .cartesian_product(min.y..=max.y)
-            .map(|(x, y)| Point::new(x, y))
-            .collect::<HashSet<_>>()
-            .difference(&self.dug)
-            .copied()
-            .collect::<HashSet<_>>();
-
-        while !all_points.is_empty() {
-            let (is_inside, found) = self._flood_fill(all_points.iter().next().unwrap());
-            all_points = all_points.difference(&found).copied().collect();
-            if is_inside {
-                self.dug.extend(found);
-            }
-        }
-    }
-
-    // fill in adjacent cells and report if they are inside or outside
-    fn _flood_fill(&self, start: &Point) -> (bool, HashSet<Point>) {
-        let mut is_inside = true;
-        let mut stack = vec![*start];
-        let mut res = HashSet::<Point>::new();
-        let mut seen = HashSet::<Point>::new();
-        let diags = [
-            Dir::North + Dir::East,
-            Dir::North + Dir::West,
-            Dir::South + Dir::East,
-            Dir::South + Dir::West,
-        ];
-        let dir_offsets = Dir::iter().map(|d| d.offset()).collect_vec();
-        while let Some(cur) = stack.pop() {
-            if seen.contains(&cur) {
-                continue;
-            }
-
-            seen.insert(cur);
-
-            if !(self._valid_point(&cur)) {
-                is_inside = false;
-                continue;
-            }
-            if self.dug.contains(&cur) {
-                continue;
-            }
-
-            stack.extend(chain(dir_offsets.iter(), &diags).map(|p| cur + *p));
-            res.insert(cur);
-        }
-        (is_inside, res)
-    }
-
-    fn _valid_point(&self, p: &Point) -> bool {
-        let max = self.max_point.unwrap();
-        let min = self.min_point.unwrap();
-        !(p.x < min.x || p.y < min.y || p.x > max.x || p.y > max.y)
+        let (width, height) = (max.x as usize + 1, max.y as usize + 1);
+        let data = (0..height)
+            .cartesian_product(0..width)
+            .map(|(y, x)| self.dug.contains(&Point::new(x as i32, y as i32)))
+            .collect();
+        let grid = Grid::new(width, height, data);
+
+        let regions = label_regions(&grid, |dug| !dug, Connectivity::Eight);
+        let inside = regions
+            .enclosed()
+            .flat_map(|r| {
+                r.cells
+                    .iter()
+                    .map(|(x, y)| Point::new(*x as i32, *y as i32))
+            })
+            .collect_vec();
+        self.dug.extend(inside);
     }
 
     fn _populate_instructions(&mut self) {
@@ -135,7 +102,7 @@ impl DigPlan {
         self.dug.extend(
             self.instructions
                 .iter()
-                .flat_map(|inst| repeat(inst.dir).take(inst.num as usize))
+                .flat_map(|inst| repeat_n(inst.dir, inst.num as usize))
                 .scan(Point::new(0, 0), |acc, dir| {
                     *acc = *acc + dir;
                     Some(*acc)
@@ -234,4 +201,19 @@ mod test {
         plan.excavate();
         assert_eq!(plan.dug.len(), 62);
     }
+
+    #[test]
+    fn test_corner_touch() {
+        // the trench touches itself at a corner, leaving one outside cell boxed in on all
+        // four sides but open to the outside diagonally
+        let plan = [
+            "D 1", "L 1", "D 2", "R 2", "U 1", "R 2", "D 3", "L 6", "U 6", "R 3", "D 1",
+        ]
+        .map(|s| format!("{s} (#000000)"))
+        .join("\n");
+        let mut plan = DigPlan::from_str(plan);
+        plan.execute();
+        plan.excavate();
+        assert_eq!(plan.dug.len(), 39);
+    }
 }
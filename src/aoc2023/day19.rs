@@ -1,33 +1,514 @@
-use crate::utils::read_file23;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
-pub type AocRes = Result<i32, String>;
+use itertools::Itertools;
+
+use crate::{interval::IntervalSet, utils::try_read_file};
+
+pub type AocRes = Result<u64, String>;
 
 pub fn main() -> (AocRes, AocRes) {
     (part1(), part2())
 }
 
 fn part1() -> AocRes {
-    Err("python".to_string())
+    let (system, parts) = _get_data("19.txt")?;
+    let mut res = 0;
+    for p in &parts {
+        if system.accepts(p)? {
+            res += p.total();
+        }
+    }
+    Ok(res)
 }
 
+/// every combination of ratings from 1 to 4000 that ends up accepted
 fn part2() -> AocRes {
-    Err("python".to_string())
+    let (system, _) = _get_data("19.txt")?;
+    let count = system.count_accepted(PartRanges::full(1..=4000));
+    u64::try_from(count).map_err(|_| format!("{count} doesn't fit in a u64"))
+}
+
+fn _get_data(fname: &str) -> Result<(System, Vec<Part>), String> {
+    let data = try_read_file(fname, 2023)?.join("\n");
+    let (workflows, parts) = data
+        .split_once("\n\n")
+        .ok_or("expected workflows, a blank line, then parts")?;
+    let parts = parts.lines().map(Part::from_str).try_collect()?;
+    Ok((System::from_str(workflows)?, parts))
+}
+
+// =============================================================================
+// STRUCTS/ENUMS
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    X,
+    M,
+    A,
+    S,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Less,
+    Greater,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Accept,
+    Reject,
+    Workflow(String),
+}
+
+/// e.g. `a<2006`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cond {
+    category: Category,
+    op: Op,
+    value: u64,
+}
+
+/// `cond:target`, or just `target` for the fallback at the end of a workflow
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    cond: Option<Cond>,
+    target: Target,
 }
 
-fn _get_data(fname: &str) {
-    let _lines = read_file23(fname);
+#[derive(Debug, Clone)]
+struct Workflow {
+    name: String,
+    rules: Vec<Rule>,
+}
+
+/// every workflow by name. parts start at `in`
+#[derive(Debug)]
+struct System {
+    workflows: HashMap<String, Workflow>,
 }
 
 #[derive(Debug)]
 struct Part {
-    x: i32,
-    m: i32,
-    a: i32,
-    s: i32,
+    x: u64,
+    m: u64,
+    a: u64,
+    s: u64,
+}
+
+/// possible ratings for each category, in `Category` order
+#[derive(Debug, Clone)]
+struct PartRanges([IntervalSet<u64>; 4]);
+
+// =============================================================================
+// IMPLs
+// =============================================================================
+
+impl System {
+    const START: &'static str = "in";
+
+    /// `Err` if the part falls off the end of a workflow or goes round in circles, neither
+    /// of which `from_str` lets through
+    fn accepts(&self, part: &Part) -> Result<bool, String> {
+        let mut name = Self::START;
+        // visiting more workflows than there are means one came up twice
+        for _ in 0..self.workflows.len() {
+            let rule = self.workflows[name]
+                .rules
+                .iter()
+                .find(|r| r.cond.is_none_or(|c| c.matches(part)))
+                .ok_or(format!("no rule in {name} matches {part:?}"))?;
+            match &rule.target {
+                Target::Accept => return Ok(true),
+                Target::Reject => return Ok(false),
+                Target::Workflow(next) => name = next,
+            }
+        }
+        Err(format!("{part:?} goes round in circles"))
+    }
+
+    /// a workflow that can lead back to itself, if any
+    fn _cycle(&self) -> Option<&str> {
+        let mut done = HashSet::new();
+        self.workflows
+            .keys()
+            .find_map(|name| self._cycle_from(name, &mut vec![], &mut done))
+    }
+
+    /// depth first, with `path` the workflows leading to `name` and `done` the ones that
+    /// can't reach a cycle
+    fn _cycle_from<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Option<&'a str> {
+        if done.contains(name) {
+            return None;
+        }
+        if path.contains(&name) {
+            return Some(name);
+        }
+        path.push(name);
+        for rule in &self.workflows[name].rules {
+            if let Target::Workflow(next) = &rule.target {
+                if let Some(res) = self._cycle_from(next, path, done) {
+                    return Some(res);
+                }
+            }
+        }
+        path.pop();
+        done.insert(name);
+        None
+    }
+
+    /// how many distinct parts within `ranges` get accepted
+    ///
+    /// each rule splits the ranges in two: what matches goes on to the rule's target, the
+    /// rest falls through to the next rule
+    fn count_accepted(&self, ranges: PartRanges) -> u128 {
+        self._count_accepted(Self::START, ranges)
+    }
+
+    fn _count_accepted(&self, name: &str, mut ranges: PartRanges) -> u128 {
+        let mut res = 0;
+        for rule in &self.workflows[name].rules {
+            let matched = match rule.cond {
+                Some(cond) => {
+                    let (matched, rest) = ranges.split(cond);
+                    ranges = rest;
+                    matched
+                }
+                None => std::mem::replace(&mut ranges, PartRanges::empty()),
+            };
+            if matched.is_empty() {
+                continue;
+            }
+            res += match &rule.target {
+                Target::Accept => matched.count(),
+                Target::Reject => 0,
+                Target::Workflow(next) => self._count_accepted(next, matched),
+            };
+        }
+        res
+    }
+}
+
+impl Cond {
+    fn matches(&self, part: &Part) -> bool {
+        let rating = part.get(self.category);
+        match self.op {
+            Op::Less => rating < self.value,
+            Op::Greater => rating > self.value,
+        }
+    }
+}
+
+impl Part {
+    fn get(&self, category: Category) -> u64 {
+        match category {
+            Category::X => self.x,
+            Category::M => self.m,
+            Category::A => self.a,
+            Category::S => self.s,
+        }
+    }
+
+    fn total(&self) -> u64 {
+        self.x + self.m + self.a + self.s
+    }
+}
+
+impl PartRanges {
+    fn full(ratings: std::ops::RangeInclusive<u64>) -> Self {
+        Self(std::array::from_fn(|_| {
+            IntervalSet::from_range(ratings.clone())
+        }))
+    }
+
+    fn empty() -> Self {
+        Self(Default::default())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().any(IntervalSet::is_empty)
+    }
+
+    fn count(&self) -> u128 {
+        self.0
+            .iter()
+            .map(|s| s.len().expect("a set of u64 always has a u128 length"))
+            .product()
+    }
+
+    /// `(matches cond, doesn't)`
+    fn split(&self, cond: Cond) -> (Self, Self) {
+        let i = cond.category as usize;
+        let (below, above) = match cond.op {
+            Op::Less => self.0[i].split_at(cond.value),
+            Op::Greater => self.0[i].split_at(cond.value + 1),
+        };
+        let (matched, rest) = match cond.op {
+            Op::Less => (below, above),
+            Op::Greater => (above, below),
+        };
+        let (mut yes, mut no) = (self.clone(), self.clone());
+        yes.0[i] = matched;
+        no.0[i] = rest;
+        (yes, no)
+    }
+}
+
+// =============================================================================
+// PARSING
+// =============================================================================
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(Self::X),
+            "m" => Ok(Self::M),
+            "a" => Ok(Self::A),
+            "s" => Ok(Self::S),
+            _ => Err(format!("unknown category {s}")),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "A" => Self::Accept,
+            "R" => Self::Reject,
+            name => Self::Workflow(name.to_string()),
+        })
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    /// `a<2006:qkq` or `rfg`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((cond, target)) = s.split_once(':') else {
+            return Ok(Self {
+                cond: None,
+                target: Target::from_str(s)?,
+            });
+        };
+        let (pos, op) = cond
+            .char_indices()
+            .find_map(|(i, c)| match c {
+                '<' => Some((i, Op::Less)),
+                '>' => Some((i, Op::Greater)),
+                _ => None,
+            })
+            .ok_or(format!("no comparison in {s}"))?;
+        Ok(Self {
+            cond: Some(Cond {
+                category: Category::from_str(&cond[..pos])?,
+                op,
+                value: cond[pos + 1..]
+                    .parse()
+                    .map_err(|_| format!("bad value in {s}"))?,
+            }),
+            target: Target::from_str(target)?,
+        })
+    }
+}
+
+impl FromStr for Workflow {
+    type Err = String;
+
+    /// `px{a<2006:qkq,m>2090:A,rfg}`. the last rule has to be a fallback with no condition,
+    /// so every part goes somewhere
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rules) = s
+            .strip_suffix('}')
+            .and_then(|s| s.split_once('{'))
+            .ok_or(format!("bad workflow: {s}"))?;
+        let rules: Vec<Rule> = rules.split(',').map(Rule::from_str).try_collect()?;
+        if rules.last().is_none_or(|r| r.cond.is_some()) {
+            return Err(format!("no fallback rule at the end of {s}"));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            rules,
+        })
+    }
+}
+
+impl FromStr for System {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let workflows = s
+            .lines()
+            .map(|l| Workflow::from_str(l).map(|w| (w.name.clone(), w)))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        if !workflows.contains_key(Self::START) {
+            return Err(format!("no `{}` workflow", Self::START));
+        }
+        let missing = workflows
+            .values()
+            .flat_map(|w| &w.rules)
+            .filter_map(|r| match &r.target {
+                Target::Workflow(name) if !workflows.contains_key(name) => Some(name),
+                _ => None,
+            })
+            .cloned()
+            .collect_vec();
+        if !missing.is_empty() {
+            return Err(format!("unknown workflows: {missing:?}"));
+        }
+        let system = Self { workflows };
+        if let Some(name) = system._cycle() {
+            return Err(format!("{name} can lead back to itself"));
+        }
+        Ok(system)
+    }
+}
+
+impl FromStr for Part {
+    type Err = String;
+
+    /// `{x=787,m=2655,a=1222,s=2876}`, in any order but with each category exactly once
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ratings = [None; 4];
+        let inner = s
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .ok_or(format!("bad part: {s}"))?;
+        for kv in inner.split(',') {
+            let (k, v) = kv.split_once('=').ok_or(format!("bad rating in {s}"))?;
+            let rating = &mut ratings[Category::from_str(k)? as usize];
+            if rating.is_some() {
+                return Err(format!("{k} rated twice in {s}"));
+            }
+            *rating = Some(v.parse().map_err(|_| format!("bad rating in {s}"))?);
+        }
+        let [x, m, a, s] = ratings.map(|r| r.ok_or_else(|| format!("missing a rating in {s}")));
+        Ok(Self {
+            x: x?,
+            m: m?,
+            a: a?,
+            s: s?,
+        })
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
-}
\ No newline at end of file
+    #[test]
+    fn test_parse() {
+        let w = Workflow::from_str("px{a<2006:qkq,m>2090:A,rfg}").unwrap();
+        assert_eq!(w.name, "px");
+        assert_eq!(
+            w.rules[1],
+            Rule {
+                cond: Some(Cond {
+                    category: Category::M,
+                    op: Op::Greater,
+                    value: 2090
+                }),
+                target: Target::Accept
+            }
+        );
+        assert_eq!(w.rules[2].target, Target::Workflow("rfg".to_string()));
+        assert_eq!(
+            Part::from_str("{x=787,m=2655,a=1222,s=2876}")
+                .unwrap()
+                .total(),
+            7540
+        );
+        assert!(System::from_str("in{a<5:nope,R}").is_err());
+        assert!(System::from_str("in{a<5:A}").is_err());
+        assert!(System::from_str("in{R,a<5:A}").is_err());
+        assert!(System::from_str("in{a<5:b,R}\nb{x>3:c,A}\nc{b}").is_err());
+        assert!(System::from_str("in{in}").is_err());
+        assert!(System::from_str("in{a<5:b,c}\nb{x>3:c,A}\nc{R}").is_ok());
+
+        let p = Part::from_str("{s=4,a=3,x=1,m=2}").unwrap();
+        assert_eq!((p.x, p.m, p.a, p.s), (1, 2, 3, 4));
+        assert!(Part::from_str("{x=1,m=2,a=3}").is_err());
+        assert!(Part::from_str("{x=1,m=2,a=3,x=4}").is_err());
+        assert!(Part::from_str("{x=1,m=2,a=3,q=4}").is_err());
+    }
+
+    #[test]
+    fn test_accepts_errors() {
+        let part = Part::from_str("{x=1,m=2,a=3,s=4}").unwrap();
+        // built by hand, skipping the checks in `from_str`
+        let system = |workflows: &[(&str, &str)]| System {
+            workflows: workflows
+                .iter()
+                .map(|(name, rules)| {
+                    let w = Workflow {
+                        name: name.to_string(),
+                        rules: rules
+                            .split(',')
+                            .map(|r| Rule::from_str(r).unwrap())
+                            .collect(),
+                    };
+                    (name.to_string(), w)
+                })
+                .collect(),
+        };
+        assert!(system(&[("in", "a<2:A")]).accepts(&part).is_err());
+        assert!(system(&[("in", "a>2:b,R"), ("b", "in")])
+            .accepts(&part)
+            .is_err());
+        assert_eq!(
+            system(&[("in", "a>2:b,R"), ("b", "A")]).accepts(&part),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn test_example() {
+        let (system, parts) = _get_data("19.txt.test").unwrap();
+        let accepted = parts
+            .iter()
+            .filter(|p| system.accepts(p).unwrap())
+            .collect_vec();
+        assert_eq!(accepted.len(), 3);
+        assert_eq!(accepted.iter().map(|p| p.total()).sum::<u64>(), 19114);
+        assert_eq!(
+            system.count_accepted(PartRanges::full(1..=4000)),
+            167409079868000
+        );
+    }
+
+    #[test]
+    fn test_ranges_match_parts() {
+        // brute force a small cube of ratings
+        let (system, _) = _get_data("19.txt.test").unwrap();
+        let values = [1, 537, 538, 1415, 1416, 2090, 2091, 3448, 3449, 4000];
+        let ranges = PartRanges(std::array::from_fn(|_| {
+            values.iter().map(|v| *v..=*v).collect()
+        }));
+        let brute = itertools::iproduct!(values, values, values, values)
+            .filter(|(x, m, a, s)| {
+                system
+                    .accepts(&Part {
+                        x: *x,
+                        m: *m,
+                        a: *a,
+                        s: *s,
+                    })
+                    .unwrap()
+            })
+            .count();
+        assert_eq!(system.count_accepted(ranges), brute as u128);
+    }
+}
@@ -1,13 +1,18 @@
 use std::{
-    any::Any,
-    collections::{HashMap, HashSet, VecDeque},
-    fmt::Display,
-    time::Instant,
+    collections::{HashMap, VecDeque},
+    fmt::{Debug, Display, Write},
+    fs,
+    path::PathBuf,
 };
 
 use itertools::Itertools;
 
-use crate::{tprint, utils::read_file23};
+use crate::{
+    dot::{Dot, ToDot},
+    math::{crt, lcm_all},
+    tprint,
+    utils::{read_file23, trace_path},
+};
 
 pub type AocRes = Result<u64, String>;
 
@@ -17,50 +22,70 @@ pub fn main() -> (AocRes, AocRes) {
 
 fn part1() -> AocRes {
     let mut grid = _get_data("20.txt");
-    grid.run(1_000);
-    Ok(grid.counter.values().product())
+    let mut counter: HashMap<PulseType, u64> = HashMap::new();
+    grid.run_with(1_000, |_, pulse| {
+        *counter.entry(pulse.type_).or_default() += 1;
+        Flow::Continue
+    });
+    Ok(counter.values().product())
 }
 
-#[allow(unreachable_code)]
+/// `rx` gets a Low once the conjunction feeding it sees High from all of its inputs
+/// on the same press. each input goes High on its own cycle, so line those up
 fn part2() -> AocRes {
     let mut grid = _get_data("20.txt");
-    grid.run(10_000);
-    let min_run = grid
-        .relevant
-        .values()
-        .map(|hs| {
-            *_get_diffs(hs).first().unwrap()
-        })
-        .product::<u64>();
-    Ok(min_run)
-
-    // code to play around with cycle results
-    // grid.relevant.iter().for_each(|(k, hs)| {
-    //     let diffs = _get_diffs(hs);
-    //     let d = diffs.iter().next().unwrap();
-    //     let mut v = hs.iter().collect_vec();
-    //     v.sort();
-    //     println!("{}: start: {:?} cycle:{:?} is_prime: {}", k, &v[..2], diffs, primal::is_prime(*d));
-    // });
-    // for (source, count) in grid
-    //     .target_counter
-    //     .iter()
-    //     .sorted_by(|a, b| Ord::cmp(b.1, a.1))
-    // {
-    //     tprint!(source, count);
-    // };
-    // Err("unsolved".to_string())
-}
-
-fn _get_diffs(nums: &HashSet<u64>) -> Vec<u64> {
-    let mut res = nums.iter().collect_vec();
-    res.sort();
-    res.iter()
+    let cycles = grid.feeder_cycles("rx", 3, 100_000)?;
+    _first_alignment(&cycles).ok_or("cycles never line up".to_string())
+}
+
+/// a module that goes High on press `offset`, then every `period` presses after that
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cycle {
+    offset: u64,
+    period: u64,
+}
+
+/// a cycle is confirmed once the last `num_periods` gaps between presses all match
+fn _confirm_cycle(presses: &[u64], num_periods: usize) -> Option<Cycle> {
+    if presses.len() <= num_periods {
+        return None;
+    }
+    let (period,) = presses
+        .iter()
         .tuple_windows()
-        .map(|(a, b)| **b - **a)
-        .sorted()
+        .map(|(a, b)| b - a)
+        .skip(presses.len() - 1 - num_periods)
         .dedup()
-        .collect()
+        .collect_tuple()?;
+    let offset = presses[0];
+    presses
+        .iter()
+        .all(|p| (p - offset).is_multiple_of(period))
+        .then_some(Cycle { offset, period })
+}
+
+/// first press where every cycle fires at once
+fn _first_alignment(cycles: &[Cycle]) -> Option<u64> {
+    // the usual case: each cycle first fires after exactly one period
+    if cycles.iter().all(|c| c.offset == c.period) {
+        return Some(lcm_all(cycles.iter().map(|c| c.period)));
+    }
+    let (res, modulus) = crt(cycles.iter().map(|c| (c.offset % c.period, c.period)))?;
+    // smallest n ≡ res (mod modulus) that's at or after every offset
+    let start = cycles.iter().map(|c| c.offset).max()?;
+    Some(match res >= start {
+        true => res,
+        false => res + (start - res).div_ceil(modulus) * modulus,
+    })
+}
+
+/// press the button `num_presses` times on input `fname` and write every pulse to
+/// `trace/2023/<fname>.trace`, for digging through by hand. not used by either part
+pub fn write_trace(fname: &str, num_presses: u64) -> Result<PathBuf, String> {
+    _get_data(fname)
+        .record(num_presses)
+        .write(&format!("{fname}.trace"), 2023)
+        .map_err(|e| e.to_string())
 }
 
 fn _get_data(fname: &str) -> Grid {
@@ -71,21 +96,28 @@ fn _get_data(fname: &str) -> Grid {
 // ENUMS
 // =============================================================================
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
-enum ModuleType {
-    FlipFlop,
-    Conjunction,
-    Broadcast,
-    Button,
-}
-
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
 enum PulseType {
     Low,
     High,
 }
 
+/// returned from pulse hooks to keep going or stop the simulation right away
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum Flow {
+    Continue,
+    Stop,
+}
+
 impl PulseType {
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "Low" => Ok(PulseType::Low),
+            "High" => Ok(PulseType::High),
+            _ => Err(format!("unknown pulse type {s}")),
+        }
+    }
+
     fn flip(&self) -> PulseType {
         match self {
             PulseType::Low => PulseType::High,
@@ -94,12 +126,20 @@ impl PulseType {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
 enum OnOff {
     On,
     Off,
 }
 impl OnOff {
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "On" => Ok(OnOff::On),
+            "Off" => Ok(OnOff::Off),
+            _ => Err(format!("unknown flip-flop state {s}")),
+        }
+    }
+
     fn flip(&self) -> Self {
         match self {
             OnOff::On => OnOff::Off,
@@ -112,75 +152,109 @@ impl OnOff {
 // STRUCTS
 // =============================================================================
 
-#[derive(Debug, Eq, PartialEq)]
+/// index of a module in the `Grid`. names are only needed for parsing and display
+type ModuleId = usize;
+
+/// index of a kind of module in `Grid::kinds`
+type KindId = usize;
+
+/// every network has a button to press and sinks for targets with no definition, so
+/// these two kinds always come first
+const BUTTON: KindId = 0;
+const SINK: KindId = 1;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
 /// a pulse being sent from a module to another module
 struct Pulse {
-    source: String,
-    target: String,
+    source: ModuleId,
+    target: ModuleId,
     type_: PulseType,
 }
 
-impl Display for Pulse {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} -{:?}-> {}", self.source, self.type_, self.target)
-    }
-}
-
-/// a communication device that receives/sends signals
+/// a parsed line of the input: `%name -> a, b`
 #[derive(Debug)]
 struct Module {
-    type_: ModuleType,
+    kind: KindId,
     name: String,
     targets: Vec<String>,
 }
 
 /// on low state, flip the current pulse type and emit that to all children
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct FlipFlop {
-    module: Module,
-    state: OnOff,
+    on: Vec<bool>,
 }
 
 /// if last-received pulses from all its upstream modules are High, then emit a Low pulse signal
 /// otherwise, emit a High pulse
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct Conjunction {
-    module: Module,
-    inputs: HashMap<String, PulseType>,
+    /// whether each input (by slot) last sent High
+    high: Vec<Vec<bool>>,
+    /// how many of `high` are set
+    num_high: Vec<usize>,
 }
 
 /// emit received pulse to all targets
 #[derive(Debug)]
-struct Broadcast {
-    module: Module,
-}
+struct Broadcast;
+
+/// emit a single low pulse to the `broadcaster`
+#[derive(Debug)]
+struct Button;
+
+/// a target with no module definition, e.g. `rx`. swallows every pulse
+#[derive(Debug)]
+struct Sink;
 
 /// layout of the all of the communication modules
+///
+/// modules live in a `Vec` indexed by `ModuleId` with their wiring kept alongside,
+/// so sending a pulse is just a few integer lookups
 #[derive(Debug)]
 struct Grid {
-    modules: HashMap<String, Box<dyn ModuleTrait>>,
-    pulses: VecDeque<Pulse>,
-    counter: HashMap<PulseType, u64>,
-    unknown: HashMap<String, Vec<Pulse>>,
-    relevant: HashMap<String, HashSet<u64>>,
+    names: Vec<String>,
+    ids: HashMap<String, ModuleId>,
+    kinds: Vec<Box<dyn ModuleTrait>>,
+    /// each module's kind, and its index among the modules of that kind
+    modules: Vec<(KindId, usize)>,
+    targets: Vec<Vec<ModuleId>>,
+    /// `slots[source][i]` is which of `targets[source][i]`'s inputs that edge is
+    slots: Vec<Vec<usize>>,
+    inputs: Vec<Vec<ModuleId>>,
+    /// pulses waiting to be delivered, along with the input slot they arrive on
+    pulses: VecDeque<(Pulse, usize)>,
+    presses: u64,
+    button: ModuleId,
 }
 
-/// emit a single low pulse to the `broadcaster`
-#[derive(Debug)]
-struct Button {
-    module: Module,
+/// a delivered pulse along with when it happened
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+struct TraceEntry {
+    press: u64,
+    /// how many pulses were delivered earlier in the same press
+    position: usize,
+    pulse: Pulse,
 }
 
-impl Button {
-    fn new() -> Self {
-        Self {
-            module: Module {
-                type_: ModuleType::Button,
-                name: String::from("button"),
-                targets: vec![String::from("broadcaster")],
-            },
-        }
-    }
+/// a flip-flop turning on or off
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+struct Transition {
+    press: u64,
+    position: usize,
+    state: OnOff,
+}
+
+/// every pulse from a run of button presses, from `Grid::record`
+///
+/// keeps its own copy of module names, kinds and starting flip-flop states so it can be
+/// written out and read back without the network
+#[derive(Debug, Eq, PartialEq)]
+struct Trace {
+    names: Vec<String>,
+    kinds: Vec<String>,
+    initial: Vec<Option<OnOff>>,
+    entries: Vec<TraceEntry>,
 }
 
 // =============================================================================
@@ -188,247 +262,782 @@ impl Button {
 // =============================================================================
 
 impl Grid {
-    fn emit(&mut self, pulse: Pulse) {
-        self.pulses.push_back(pulse);
+    fn run(&mut self, num_presses: u64) {
+        self.run_with(num_presses, |_, _| Flow::Continue);
     }
 
-    fn run(&mut self, num_presses: u64) {
+    /// press the button `num_presses` times, calling `hook` with the (1-based) press number
+    /// and every pulse just before it's delivered. returns the number of presses completed
+    fn run_with(&mut self, num_presses: u64, mut hook: impl FnMut(u64, &Pulse) -> Flow) -> u64 {
         for i in 0..num_presses {
-            self._press_button();
-            self._process(i);
+            self.presses += 1;
+            self._send(self.button, PulseType::Low);
+            if self._process(&mut hook) == Flow::Stop {
+                self.pulses.clear();
+                return i + 1;
+            }
         }
+        num_presses
     }
 
-    fn _process(&mut self, button_press_num: u64) {
-        while let Some(pulse) = self.pulses.pop_front() {
-            if pulse.type_ == PulseType::High {
-                if let Some(v) = self.relevant.get_mut(pulse.source.as_str()) {
-                    v.insert(button_press_num);
-                }
+    /// press the button `num_presses` times, keeping every pulse
+    fn record(&mut self, num_presses: u64) -> Trace {
+        let mut trace = Trace {
+            names: self.names.clone(),
+            kinds: (0..self.modules.len())
+                .map(|id| self.kind(id).name().to_string())
+                .collect(),
+            initial: (0..self.modules.len()).map(|id| self.state(id)).collect(),
+            entries: vec![],
+        };
+        let mut position = 0;
+        self.run_with(num_presses, |press, pulse| {
+            if trace.entries.last().is_some_and(|e| e.press != press) {
+                position = 0;
             }
-            *self.counter.entry(pulse.type_).or_default() += 1;
-            if let Some(target) = self.modules.get_mut(&pulse.target) {
-                self.pulses.extend(target.receive(pulse));
-            } else {
-                self.unknown
-                    .entry(pulse.target.clone())
-                    .or_default()
-                    .push(pulse);
+            trace.entries.push(TraceEntry {
+                press,
+                position,
+                pulse: *pulse,
+            });
+            position += 1;
+            Flow::Continue
+        });
+        trace
+    }
+
+    fn id(&self, name: &str) -> Option<ModuleId> {
+        self.ids.get(name).copied()
+    }
+
+    fn name(&self, id: ModuleId) -> &str {
+        &self.names[id]
+    }
+
+    fn kind(&self, id: ModuleId) -> &dyn ModuleTrait {
+        self.kinds[self.modules[id].0].as_ref()
+    }
+
+    /// on/off for flip-flops, `None` for anything else
+    fn state(&self, id: ModuleId) -> Option<OnOff> {
+        let (kind, index) = self.modules[id];
+        self.kinds[kind].state(index)
+    }
+
+    /// the conjunction sending to `sink` along with every module that feeds it
+    fn feeders(&self, sink: &str) -> Result<(ModuleId, Vec<ModuleId>), String> {
+        let sink_id = self.id(sink).ok_or(format!("unknown module {sink}"))?;
+        let conj = *self.inputs[sink_id]
+            .iter()
+            .exactly_one()
+            .map_err(|_| format!("expected exactly one module feeding {sink}"))?;
+        if self.kind(conj).name() != Conjunction::NAME {
+            return Err(format!(
+                "{} feeding {sink} is not a conjunction",
+                self.name(conj)
+            ));
+        }
+        Ok((conj, self.inputs[conj].clone()))
+    }
+
+    /// keep pressing until every input of the conjunction feeding `sink` has gone High
+    /// at evenly spaced presses `num_periods` times in a row
+    fn feeder_cycles(
+        &mut self,
+        sink: &str,
+        num_periods: usize,
+        max_presses: u64,
+    ) -> Result<Vec<Cycle>, String> {
+        let (conj, inputs) = self.feeders(sink)?;
+        let mut high_presses: HashMap<ModuleId, Vec<u64>> =
+            inputs.iter().map(|id| (*id, vec![])).collect();
+        let mut cycles = None;
+
+        self.run_with(max_presses, |press, pulse| {
+            if pulse.target != conj || pulse.type_ != PulseType::High {
+                return Flow::Continue;
+            }
+            let presses = high_presses.get_mut(&pulse.source).unwrap();
+            if presses.last() == Some(&press) {
+                return Flow::Continue;
+            }
+            presses.push(press);
+            cycles = inputs
+                .iter()
+                .map(|id| _confirm_cycle(&high_presses[id], num_periods))
+                .collect::<Option<Vec<_>>>();
+            match cycles {
+                Some(_) => Flow::Stop,
+                None => Flow::Continue,
+            }
+        });
+        cycles.ok_or(format!(
+            "no stable cycles feeding {sink} in {max_presses} presses"
+        ))
+    }
+
+    fn _process(&mut self, hook: &mut impl FnMut(u64, &Pulse) -> Flow) -> Flow {
+        while let Some((pulse, slot)) = self.pulses.pop_front() {
+            if hook(self.presses, &pulse) == Flow::Stop {
+                return Flow::Stop;
+            }
+            let (kind, index) = self.modules[pulse.target];
+            if let Some(out) = self.kinds[kind].receive(index, slot, pulse.type_) {
+                self._send(pulse.target, out);
             }
         }
+        Flow::Continue
     }
-    fn _press_button(&mut self) {
-        let mut button = Button::new();
-        self.pulses.extend(button.receive(Pulse {
-            source: "grid".to_owned(),
-            target: "button".to_owned(),
-            type_: PulseType::Low,
+
+    fn _send(&mut self, source: ModuleId, type_: PulseType) {
+        let edges = self.targets[source].iter().zip(&self.slots[source]);
+        self.pulses.extend(edges.map(|(target, slot)| {
+            let pulse = Pulse {
+                source,
+                target: *target,
+                type_,
+            };
+            (pulse, *slot)
         }));
     }
 
     fn from_str(s: impl AsRef<str>) -> Self {
-        let mut modules = s
+        Self::with_kinds(s, _kinds())
+    }
+
+    /// parse a network that can use any of `kinds`, which must start with `Button` and `Sink`
+    fn with_kinds(s: impl AsRef<str>, mut kinds: Vec<Box<dyn ModuleTrait>>) -> Self {
+        let mut defs = s
             .as_ref()
             .split('\n')
-            .map(Module::from_str)
-            .map(|m| (m.name(), m))
+            .map(|line| Module::from_str(line, &kinds))
+            .collect_vec();
+        defs.push(Module {
+            kind: BUTTON,
+            name: "button".to_string(),
+            targets: vec!["broadcaster".to_string()],
+        });
+
+        // every target needs an id, even if it has no definition
+        let names = defs
+            .iter()
+            .map(|m| &m.name)
+            .chain(defs.iter().flat_map(|m| &m.targets))
+            .unique()
+            .cloned()
+            .collect_vec();
+        let ids: HashMap<_, _> = names.iter().cloned().zip(0..).collect();
+
+        let mut module_kinds = vec![SINK; names.len()];
+        let mut targets = vec![vec![]; names.len()];
+        for m in &defs {
+            let id = ids[&m.name];
+            module_kinds[id] = m.kind;
+            targets[id] = m.targets.iter().map(|t| ids[t]).collect();
+        }
+
+        // each edge's slot is its position in the target's inputs
+        let mut inputs = vec![vec![]; names.len()];
+        let mut slots = vec![vec![]; names.len()];
+        for (source, ts) in targets.iter().enumerate() {
+            for t in ts {
+                slots[source].push(inputs[*t].len());
+                inputs[*t].push(source);
+            }
+        }
+        let modules = module_kinds
+            .iter()
+            .zip(&inputs)
+            .map(|(kind, i)| (*kind, kinds[*kind].add(i.len())))
             .collect();
 
-        Self::_update_conjunctions(&mut modules);
-        let relevant = hashmap! {
-            "kh".to_string() => HashSet::<u64>::new(),
-            "hn".to_string() => HashSet::<u64>::new(),
-            "lz".to_string() => HashSet::<u64>::new(),
-            "tg".to_string() => HashSet::<u64>::new(),
-        };
         Self {
+            button: ids["button"],
+            names,
+            ids,
+            kinds,
             modules,
+            targets,
+            slots,
+            inputs,
             pulses: VecDeque::default(),
-            counter: HashMap::default(),
-            unknown: HashMap::default(),
-            relevant,
+            presses: 0,
+        }
+    }
+
+    fn dump_state(&self) {
+        tprint!(self.presses);
+        for (id, (kind, index)) in self.modules.iter().enumerate() {
+            self.kinds[*kind].dump_state(self.name(id), *index);
         }
     }
+}
 
-    fn _update_conjunctions(modules: &mut HashMap<String, Box<dyn ModuleTrait>>) {
-        let source_target_map = modules
+impl Trace {
+    fn id(&self, name: &str) -> Result<ModuleId, String> {
+        self.names
             .iter()
-            .map(|(source, m)| {
-                (
-                    source.clone(),
-                    m.targets().iter().cloned().cloned().collect_vec(),
-                )
+            .position(|n| n == name)
+            .ok_or(format!("unknown module {name}"))
+    }
+
+    /// every `type_` pulse delivered to `name`
+    fn received<'a>(
+        &'a self,
+        name: &str,
+        type_: PulseType,
+    ) -> Result<impl Iterator<Item = &'a TraceEntry>, String> {
+        let id = self.id(name)?;
+        Ok(self
+            .entries
+            .iter()
+            .filter(move |e| e.pulse.target == id && e.pulse.type_ == type_))
+    }
+
+    /// first press where `name` got a `type_` pulse
+    fn first_received(&self, name: &str, type_: PulseType) -> Result<Option<u64>, String> {
+        Ok(self.received(name, type_)?.next().map(|e| e.press))
+    }
+
+    /// every time flip-flop `name` turned on or off
+    fn transitions(&self, name: &str) -> Result<Vec<Transition>, String> {
+        let id = self.id(name)?;
+        let mut state = self.initial[id].ok_or(format!("{name} is not a flip-flop"))?;
+        Ok(self
+            .received(name, PulseType::Low)?
+            .map(|e| {
+                state = state.flip();
+                Transition {
+                    press: e.press,
+                    position: e.position,
+                    state,
+                }
             })
-            .collect::<HashMap<_, _>>();
+            .collect())
+    }
+
+    /// a `module` line per module, then `press position source type target` per pulse
+    fn render(&self) -> String {
+        let mut res = String::new();
+        for (id, name) in self.names.iter().enumerate() {
+            let _ = write!(res, "module {name} {}", self.kinds[id]);
+            if let Some(state) = self.initial[id] {
+                let _ = write!(res, " {state:?}");
+            }
+            res.push('\n');
+        }
+        for e in &self.entries {
+            let p = e.pulse;
+            let _ = writeln!(
+                res,
+                "{} {} {} {:?} {}",
+                e.press, e.position, self.names[p.source], p.type_, self.names[p.target]
+            );
+        }
+        res
+    }
+
+    fn write(&self, fname: &str, year: u16) -> std::io::Result<PathBuf> {
+        let path = trace_path(fname, year);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, self.render())?;
+        Ok(path)
+    }
 
-        for (source, targets) in source_target_map.iter() {
-            for t in targets {
-                if let Some(m) = modules.get_mut(t) {
-                    m.add_input(&source);
+    fn read(fname: &str, year: u16) -> Result<Self, String> {
+        let s = fs::read_to_string(trace_path(fname, year)).map_err(|e| e.to_string())?;
+        Self::from_str(&s)
+    }
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut trace = Trace {
+            names: vec![],
+            kinds: vec![],
+            initial: vec![],
+            entries: vec![],
+        };
+        let mut ids = HashMap::new();
+        for line in s.lines() {
+            let fields = line.split(' ').collect_vec();
+            match fields[..] {
+                ["module", name, kind, ref state @ ..] => {
+                    ids.insert(name.to_string(), trace.names.len());
+                    trace.names.push(name.to_string());
+                    trace.kinds.push(kind.to_string());
+                    trace.initial.push(match state {
+                        [] => None,
+                        [state] => Some(OnOff::from_str(state)?),
+                        _ => return Err(format!("bad module line: {line}")),
+                    });
+                }
+                [press, position, source, type_, target] => {
+                    let id = |name: &str| {
+                        ids.get(name)
+                            .copied()
+                            .ok_or(format!("unknown module {name}"))
+                    };
+                    trace.entries.push(TraceEntry {
+                        press: press.parse().map_err(|_| format!("bad press: {line}"))?,
+                        position: position
+                            .parse()
+                            .map_err(|_| format!("bad position: {line}"))?,
+                        pulse: Pulse {
+                            source: id(source)?,
+                            target: id(target)?,
+                            type_: PulseType::from_str(type_)?,
+                        },
+                    });
                 }
+                _ => return Err(format!("bad trace line: {line}")),
             }
         }
+        Ok(trace)
+    }
+}
+
+impl Pulse {
+    /// for display, with module names in place of ids
+    fn named<'a>(&'a self, names: &'a [String]) -> NamedPulse<'a> {
+        NamedPulse { pulse: self, names }
+    }
+}
+
+/// e.g. `a -High-> inv`
+struct NamedPulse<'a> {
+    pulse: &'a Pulse,
+    names: &'a [String],
+}
+
+impl Display for NamedPulse<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let p = self.pulse;
+        write!(
+            f,
+            "{} -{:?}-> {}",
+            self.names[p.source], p.type_, self.names[p.target]
+        )
     }
-    fn dump_state(&self, button_press_num: u64) {
-        tprint!(button_press_num);
-        self.modules.values().for_each(|v| v.dump_state());
+}
+
+/// node shapes by kind. targets that aren't modules (e.g. `rx`) are double circles
+impl ToDot for Grid {
+    fn to_dot(&self) -> Dot {
+        let mut dot = Dot::new("modules");
+        let order = (0..self.names.len())
+            .sorted_by_key(|id| self.name(*id))
+            .collect_vec();
+        for id in &order {
+            let shape = self.kind(*id).dot_shape();
+            dot.node(self.name(*id), &[("shape", shape)]);
+        }
+        for id in &order {
+            for t in &self.targets[*id] {
+                dot.edge(self.name(*id), self.name(*t), None);
+            }
+        }
+        dot
     }
 }
 
 // =============================================================================
 // TRAITS
 // =============================================================================
-use std::fmt::Debug;
 
+/// implement this, and pass it to `Grid::with_kinds`, to add a new kind of gate
+///
+/// there's one of these per kind rather than per module. it holds whatever every module of
+/// its kind needs to remember, in arrays indexed by the `index` handed out from `add`
 trait ModuleTrait: Debug {
-    fn receive(&mut self, pulse: Pulse) -> Vec<Pulse>;
-    fn name(&self) -> String;
-    fn targets(&self) -> Vec<&String>;
-    fn module_type(&self) -> ModuleType;
-    fn generate_pulses(&self, targets: &[String], pulse_type: PulseType) -> Vec<Pulse> {
-        targets
-            .iter()
-            .map(|t| Pulse {
-                source: self.name(),
-                target: t.to_owned(),
-                type_: pulse_type,
-            })
-            .collect()
+    /// written to traces, e.g. `FlipFlop`
+    fn name(&self) -> &'static str;
+
+    fn dot_shape(&self) -> &'static str;
+
+    /// the module's name if `def`, the part of a line before ` -> `, is one of these
+    fn parse<'a>(&self, _def: &'a str) -> Option<&'a str> {
+        None
+    }
+
+    /// make room for another module with `num_inputs` inputs, returning its index
+    fn add(&mut self, _num_inputs: usize) -> usize {
+        0
+    }
+
+    /// handle a pulse arriving at module `index` on input `slot`, returning what (if anything)
+    /// to send to every target
+    fn receive(&mut self, index: usize, slot: usize, pulse: PulseType) -> Option<PulseType>;
+
+    /// on/off for flip-flops, `None` for anything else
+    fn state(&self, _index: usize) -> Option<OnOff> {
+        None
     }
-    fn add_input(&mut self, _s: &String) {}
-    fn dump_state(&self);
+
+    fn dump_state(&self, _name: &str, _index: usize) {}
+}
+
+/// the kinds in the puzzle input
+fn _kinds() -> Vec<Box<dyn ModuleTrait>> {
+    vec![
+        Box::new(Button),
+        Box::new(Sink),
+        Box::new(Broadcast),
+        Box::<FlipFlop>::default(),
+        Box::<Conjunction>::default(),
+    ]
 }
 
 impl Module {
-    fn from_str(s: &str) -> Box<dyn ModuleTrait> {
-        let (name, target_str) = s.split_once(" -> ").unwrap();
-        let c = name.chars().next().unwrap();
-        let (module_type, name) = match c {
-            'b' => (ModuleType::Broadcast, name),
-            '%' => (ModuleType::FlipFlop, &name[1..]),
-            '&' => (ModuleType::Conjunction, &name[1..]),
-            _ => panic!("unmatched char in ModuleTrait: {c}"),
-        };
+    fn from_str(s: &str, kinds: &[Box<dyn ModuleTrait>]) -> Self {
+        let (def, target_str) = s.split_once(" -> ").unwrap();
+        let (kind, name) = kinds
+            .iter()
+            .enumerate()
+            .find_map(|(kind, k)| k.parse(def).map(|name| (kind, name)))
+            .unwrap_or_else(|| panic!("unknown module: {def}"));
 
-        let module = Module {
-            type_: module_type,
+        Module {
+            kind,
             name: name.to_owned(),
             targets: target_str.split(", ").map(|s| s.to_owned()).collect(),
-        };
-
-        match module_type {
-            ModuleType::FlipFlop => Box::new(FlipFlop {
-                module,
-                state: OnOff::Off,
-            }),
-            ModuleType::Conjunction => Box::new(Conjunction {
-                module,
-                inputs: HashMap::default(),
-            }),
-            ModuleType::Broadcast => Box::new(Broadcast { module }),
-            ModuleType::Button => panic!("should not receive a button here!"),
         }
     }
 }
 
 impl ModuleTrait for Button {
-    fn receive(&mut self, pulse: Pulse) -> Vec<Pulse> {
-        vec![Pulse {
-            source: "button".to_owned(),
-            target: "broadcaster".to_owned(),
-            ..pulse
-        }]
+    fn name(&self) -> &'static str {
+        "Button"
     }
 
-    fn name(&self) -> String {
-        String::from("button")
+    fn dot_shape(&self) -> &'static str {
+        "circle"
     }
 
-    fn targets(&self) -> Vec<&String> {
-        self.module.targets.iter().collect()
+    fn receive(&mut self, _: usize, _: usize, _: PulseType) -> Option<PulseType> {
+        Some(PulseType::Low)
     }
+}
 
-    fn module_type(&self) -> ModuleType {
-        self.module.type_
+impl ModuleTrait for Broadcast {
+    fn name(&self) -> &'static str {
+        "Broadcast"
     }
 
-    fn dump_state(&self) {}
-}
+    fn dot_shape(&self) -> &'static str {
+        "doubleoctagon"
+    }
 
-impl ModuleTrait for Broadcast {
-    fn receive(&mut self, pulse: Pulse) -> Vec<Pulse> {
-        self.generate_pulses(&self.module.targets, pulse.type_)
+    fn parse<'a>(&self, def: &'a str) -> Option<&'a str> {
+        (def == "broadcaster").then_some(def)
     }
 
-    fn name(&self) -> String {
-        self.module.name.clone()
+    fn receive(&mut self, _: usize, _: usize, pulse: PulseType) -> Option<PulseType> {
+        Some(pulse)
     }
+}
 
-    fn targets(&self) -> Vec<&String> {
-        self.module.targets.iter().collect()
+impl ModuleTrait for Sink {
+    fn name(&self) -> &'static str {
+        "Sink"
     }
-    fn module_type(&self) -> ModuleType {
-        self.module.type_
+
+    fn dot_shape(&self) -> &'static str {
+        "doublecircle"
     }
 
-    fn dump_state(&self) {}
+    fn receive(&mut self, _: usize, _: usize, _: PulseType) -> Option<PulseType> {
+        None
+    }
 }
 
 impl ModuleTrait for FlipFlop {
-    fn receive(&mut self, pulse: Pulse) -> Vec<Pulse> {
-        if pulse.type_ == PulseType::High {
-            return Vec::new();
-        }
+    fn name(&self) -> &'static str {
+        "FlipFlop"
+    }
 
-        self.state = self.state.flip();
+    fn dot_shape(&self) -> &'static str {
+        "box"
+    }
 
-        self.generate_pulses(
-            &self.module.targets,
-            match self.state {
-                OnOff::On => PulseType::High,
-                OnOff::Off => PulseType::Low,
-            },
-        )
+    fn parse<'a>(&self, def: &'a str) -> Option<&'a str> {
+        def.strip_prefix('%')
     }
-    fn name(&self) -> String {
-        self.module.name.clone()
+
+    fn add(&mut self, _num_inputs: usize) -> usize {
+        self.on.push(false);
+        self.on.len() - 1
     }
-    fn targets(&self) -> Vec<&String> {
-        self.module.targets.iter().collect()
+
+    fn receive(&mut self, index: usize, _slot: usize, pulse: PulseType) -> Option<PulseType> {
+        if pulse == PulseType::High {
+            return None;
+        }
+
+        let on = &mut self.on[index];
+        *on = !*on;
+        Some(match on {
+            true => PulseType::High,
+            false => PulseType::Low,
+        })
     }
-    fn module_type(&self) -> ModuleType {
-        self.module.type_
+
+    fn state(&self, index: usize) -> Option<OnOff> {
+        Some(match self.on[index] {
+            true => OnOff::On,
+            false => OnOff::Off,
+        })
     }
 
-    fn dump_state(&self) {
-        println!("name: {}, state: {:?}", self.module.name, self.state);
+    fn dump_state(&self, name: &str, index: usize) {
+        println!("name: {}, state: {:?}", name, self.state(index).unwrap());
     }
 }
 
+impl Conjunction {
+    const NAME: &'static str = "Conjunction";
+}
+
 impl ModuleTrait for Conjunction {
-    fn receive(&mut self, pulse: Pulse) -> Vec<Pulse> {
-        let t = self.inputs.get_mut(&pulse.source).unwrap();
-        *t = pulse.type_;
-        let output_type = match self.inputs.values().all(|v| *v == PulseType::High) {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn dot_shape(&self) -> &'static str {
+        "diamond"
+    }
+
+    fn parse<'a>(&self, def: &'a str) -> Option<&'a str> {
+        def.strip_prefix('&')
+    }
+
+    fn add(&mut self, num_inputs: usize) -> usize {
+        self.high.push(vec![false; num_inputs]);
+        self.num_high.push(0);
+        self.high.len() - 1
+    }
+
+    fn receive(&mut self, index: usize, slot: usize, pulse: PulseType) -> Option<PulseType> {
+        let high = &mut self.high[index][slot];
+        match (pulse, *high) {
+            (PulseType::High, false) => self.num_high[index] += 1,
+            (PulseType::Low, true) => self.num_high[index] -= 1,
+            _ => {}
+        }
+        *high = pulse == PulseType::High;
+        let all_high = self.num_high[index] == self.high[index].len();
+        Some(match all_high {
             true => PulseType::Low,
             false => PulseType::High,
-        };
-        self.generate_pulses(&self.module.targets, output_type)
+        })
+    }
+
+    fn dump_state(&self, name: &str, index: usize) {
+        println!("name: {}, high: {:?}", name, self.high[index]);
     }
-    fn name(&self) -> String {
-        self.module.name.clone()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_feeders() {
+        let grid = _get_data("20.txt.test2");
+        let (conj, inputs) = grid.feeders("output").unwrap();
+        assert_eq!(grid.name(conj), "con");
+        let inputs = inputs
+            .iter()
+            .map(|id| grid.name(*id))
+            .sorted()
+            .collect_vec();
+        assert_eq!(inputs, vec!["a", "b"]);
+        assert!(grid.feeders("con").is_err());
+    }
+
+    #[test]
+    fn test_confirm_cycle() {
+        assert_eq!(_confirm_cycle(&[4, 8, 12], 3), None);
+        assert_eq!(
+            _confirm_cycle(&[4, 8, 12, 16], 3),
+            Some(Cycle {
+                offset: 4,
+                period: 4
+            })
+        );
+        assert_eq!(_confirm_cycle(&[4, 8, 12, 17], 3), None);
+        assert_eq!(_confirm_cycle(&[3, 8, 12, 16, 20], 3), None);
     }
-    fn targets(&self) -> Vec<&String> {
-        self.module.targets.iter().collect()
+
+    #[test]
+    fn test_first_alignment() {
+        let c = |offset, period| Cycle { offset, period };
+        assert_eq!(_first_alignment(&[c(3, 3), c(4, 4), c(6, 6)]), Some(12));
+        // x ≡ 1 (mod 3), x ≡ 2 (mod 4) -> 10
+        assert_eq!(_first_alignment(&[c(1, 3), c(2, 4)]), Some(10));
+        // non-coprime: x ≡ 2 (mod 4), x ≡ 4 (mod 6) -> 10
+        assert_eq!(_first_alignment(&[c(2, 4), c(4, 6)]), Some(10));
+        assert_eq!(_first_alignment(&[c(1, 4), c(2, 6)]), None);
+        // first alignment can't come before every cycle has started
+        assert_eq!(_first_alignment(&[c(14, 2), c(1, 3)]), Some(16));
     }
-    fn module_type(&self) -> ModuleType {
-        self.module.type_
+
+    #[test]
+    fn test_counts() {
+        for (fname, expected) in [("20.txt.test", 32000000), ("20.txt.test2", 11687500)] {
+            let mut grid = _get_data(fname);
+            let mut counter: HashMap<PulseType, u64> = HashMap::new();
+            grid.run_with(1000, |_, pulse| {
+                *counter.entry(pulse.type_).or_default() += 1;
+                Flow::Continue
+            });
+            assert_eq!(counter.values().product::<u64>(), expected);
+        }
     }
-    fn add_input(&mut self, s: &String) {
-        self.inputs.insert(s.clone(), PulseType::Low);
+
+    #[test]
+    fn test_stop() {
+        let mut grid = _get_data("20.txt.test2");
+        let output = grid.id("output").unwrap();
+        // `output` first sees Low on the first press
+        let presses = grid.run_with(1000, |_, pulse| match pulse.target == output {
+            true => Flow::Stop,
+            false => Flow::Continue,
+        });
+        assert_eq!(presses, 1);
+        assert!(grid.pulses.is_empty());
     }
 
-    fn dump_state(&self) {
-        println!("name: {}, inputs: {:?}", self.module.name, self.inputs);
+    #[test]
+    fn test_wide_conjunction() {
+        // more inputs than fit in a u64 mask
+        let n = 70;
+        let flip_flops = (0..n).map(|i| format!("f{i}")).join(", ");
+        let lines = std::iter::once(format!("broadcaster -> {flip_flops}"))
+            .chain((0..n).map(|i| format!("%f{i} -> con")))
+            .chain(std::iter::once("&con -> output".to_string()))
+            .join("\n");
+        let mut grid = Grid::from_str(lines);
+        let (con, output) = (grid.id("con").unwrap(), grid.id("output").unwrap());
+        assert_eq!(grid.inputs[con].len(), n);
+
+        // every flip-flop turns on during the first press, and only the last High gets
+        // a Low out of `con`
+        let mut received = vec![];
+        grid.run_with(1, |_, pulse| {
+            if pulse.target == output {
+                received.push(pulse.type_);
+            }
+            Flow::Continue
+        });
+        assert_eq!(received.len(), n);
+        assert_eq!(
+            received.iter().position(|t| *t == PulseType::Low),
+            Some(n - 1)
+        );
+    }
+
+    /// sends the opposite of whatever it gets
+    #[derive(Debug)]
+    struct Not;
+
+    impl ModuleTrait for Not {
+        fn name(&self) -> &'static str {
+            "Not"
+        }
+
+        fn dot_shape(&self) -> &'static str {
+            "invtriangle"
+        }
+
+        fn parse<'a>(&self, def: &'a str) -> Option<&'a str> {
+            def.strip_prefix('!')
+        }
+
+        fn receive(&mut self, _: usize, _: usize, pulse: PulseType) -> Option<PulseType> {
+            Some(pulse.flip())
+        }
+    }
+
+    #[test]
+    fn test_custom_kind() {
+        let lines = "broadcaster -> a, n\n%a -> output\n!n -> output";
+        let mut kinds = _kinds();
+        kinds.push(Box::new(Not));
+        let mut grid = Grid::with_kinds(lines, kinds);
+        assert_eq!(grid.kind(grid.id("n").unwrap()).name(), "Not");
+
+        let trace = grid.record(2);
+        let received = trace
+            .entries
+            .iter()
+            .filter(|e| trace.names[e.pulse.target] == "output")
+            .map(|e| e.pulse.named(&trace.names).to_string())
+            .collect_vec();
+        assert_eq!(
+            received,
+            vec![
+                "a -High-> output",
+                "n -High-> output",
+                "a -Low-> output",
+                "n -High-> output"
+            ]
+        );
+        assert_eq!(Trace::from_str(&trace.render()), Ok(trace));
+    }
+
+    #[test]
+    fn test_trace() {
+        let trace = _get_data("20.txt.test2").record(4);
+        assert_eq!(trace.first_received("output", PulseType::Low), Ok(Some(1)));
+        assert_eq!(trace.first_received("button", PulseType::Low), Ok(None));
+        assert!(trace.first_received("nope", PulseType::Low).is_err());
+
+        let first = trace.entries[0];
+        assert_eq!((first.press, first.position), (1, 0));
+        assert_eq!(
+            first.pulse.named(&trace.names).to_string(),
+            "button -Low-> broadcaster"
+        );
+
+        let states = trace
+            .transitions("a")
+            .unwrap()
+            .iter()
+            .map(|t| (t.press, t.state))
+            .collect_vec();
+        use OnOff::*;
+        assert_eq!(states, vec![(1, On), (2, Off), (3, On), (4, Off)]);
+        assert!(trace.transitions("con").is_err());
+
+        // inv only sends b a Low when a turns on, so b flips later in the same presses
+        let a = trace.transitions("a").unwrap();
+        let b = trace.transitions("b").unwrap();
+        assert_eq!(b.iter().map(|t| t.press).collect_vec(), vec![1, 3]);
+        assert!(b.iter().all(|b| a
+            .iter()
+            .any(|a| a.press == b.press && a.state == On && a.position < b.position)));
+
+        assert_eq!(Trace::from_str(&trace.render()), Ok(trace));
+    }
+
+    #[test]
+    fn test_write_trace() {
+        let path = write_trace("20.txt.test2", 4).unwrap();
+        assert_eq!(path, trace_path("20.txt.test2.trace", 2023));
+        let read = Trace::read("20.txt.test2.trace", 2023);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read, Ok(_get_data("20.txt.test2").record(4)));
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = _get_data("20.txt.test2").to_dot().render();
+        assert!(dot.contains("\"button\" [shape=\"circle\"];"));
+        assert!(dot.contains("\"broadcaster\" [shape=\"doubleoctagon\"];"));
+        assert!(dot.contains("\"a\" [shape=\"box\"];"));
+        assert!(dot.contains("\"inv\" [shape=\"diamond\"];"));
+        assert!(dot.contains("\"output\" [shape=\"doublecircle\"];"));
+        assert!(dot.contains("\"button\" -> \"broadcaster\";"));
     }
 }
@@ -1,14 +1,16 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
 
 use strum::IntoEnumIterator;
 
 use crate::{
-    point::{Dir, Point, point_inclusive_mod},
+    math::Differences,
+    point::{point_inclusive_mod, Dir, Point},
+    search::StateSpace,
     tprint,
     utils::read_file23,
 };
 
-pub type AocRes = Result<u32, String>;
+pub type AocRes = Result<u64, String>;
 type IsValid = fn(&Garden, &Point) -> bool;
 
 pub fn main() -> (AocRes, AocRes) {
@@ -21,19 +23,18 @@ fn part1() -> AocRes {
     };
 
     let garden = _get_data("21.txt", is_valid);
-    let res = garden.walk(64u32);
-    Ok(res.values().filter(|v| **v & 1 == 0).count() as u32)
+    Ok(garden.walk(64).at(64))
 }
 
 fn part2() -> AocRes {
-    let is_valid = |garden: &Garden, p: &Point| -> bool {
-        let p = &point_inclusive_mod(p, &garden.lower_right);
-        garden.plots.get(&PlotType::Open).unwrap().contains(p) || *p == garden.start
-    };
-    let garden = _get_data("21.txt.test", is_valid);
-    let res = garden.walk(100u32);
-    Ok(res.values().filter(|v| **v & 1 == 0).count() as u32)
-    // Err("unsolved".to_string())
+    let garden = _get_data("21.txt", _is_valid_tiled);
+    garden.extrapolate(26501365)
+}
+
+/// the garden repeats forever in every direction
+fn _is_valid_tiled(garden: &Garden, p: &Point) -> bool {
+    let p = &point_inclusive_mod(p, &garden.lower_right);
+    garden.plots.get(&PlotType::Open).unwrap().contains(p) || *p == garden.start
 }
 
 fn _get_data(fname: &str, is_valid: IsValid) -> Garden {
@@ -58,6 +59,18 @@ impl PlotType {
     }
 }
 
+/// `counts[n]` is how many plots can be ended on after exactly `n` steps
+#[derive(Debug)]
+struct Reachable {
+    counts: Vec<u64>,
+}
+
+impl Reachable {
+    fn at(&self, steps: usize) -> u64 {
+        self.counts[steps]
+    }
+}
+
 #[derive(Debug)]
 struct Garden {
     plots: HashMap<PlotType, HashSet<Point>>,
@@ -96,29 +109,112 @@ impl Garden {
         }
     }
 
-    fn walk(&self, max_count: u32) -> HashMap<Point, u32> {
-        let mut res: HashMap<Point, u32> = hashmap! {};
-        let mut to_process = VecDeque::from([(0u32, self.start)]);
-        while let Some((count, point)) = to_process.pop_front() {
-            if !(self.is_valid)(self, &point) || count > max_count {
-                continue;
-            }
-            let mut extend = true;
-            let mut insert = true;
-            if let Some(v) = res.get(&point) {
-                // if cur val is odd and count is also odd
-                (extend, insert) = match v & 1 == 1 && count & 1 == 1 {
-                    true => (false, false),
-                    false => (true, true),
-                }
-            }
-            if insert {
-                res.insert(point, count);
-            }
-            if extend {
-                to_process.extend(Dir::iter().map(|d| (count + 1, point + d)));
-            }
+    /// breadth-first, one frontier at a time, for up to `max_steps` steps
+    ///
+    /// a plot first reached after `d` steps can be ended on after any `n >= d` steps with the
+    /// same parity, by stepping back and forth. the grid is bipartite, so the next frontier is
+    /// just the neighbors of this one that weren't in the previous one
+    fn walk(&self, max_steps: usize) -> Reachable {
+        let mut prev = HashSet::new();
+        let mut frontier = HashSet::from([self.start]);
+        let mut by_parity = [1, 0];
+        let mut counts = vec![1];
+        for step in 1..=max_steps {
+            let next = frontier
+                .iter()
+                .flat_map(|p| self.successors(p))
+                .map(|(p, _)| p)
+                .filter(|p| !prev.contains(p))
+                .collect::<HashSet<_>>();
+            by_parity[step % 2] += next.len() as u64;
+            counts.push(by_parity[step % 2]);
+            prev = std::mem::replace(&mut frontier, next);
+        }
+        Reachable { counts }
+    }
+
+    /// plots reachable after `steps` on a tiled garden, without walking that far
+    ///
+    /// needs a square garden with the start in the middle, and the start's row and column
+    /// clear of rocks. then the frontier crosses one whole tile every `size` steps, so the
+    /// counts at `offset + n * size` grow quadratically with `n`. fit that from three samples.
+    /// anything else is `Err` unless `steps` is small enough to just walk
+    fn extrapolate(&self, steps: usize) -> AocRes {
+        let size = self.lower_right.x as usize + 1;
+        if self.lower_right.y as usize + 1 != size {
+            return Err("garden isn't square".to_string());
+        }
+        if self.start != Point::splat(size as i32 / 2) {
+            return Err("start isn't in the middle".to_string());
+        }
+        let (n, offset) = (steps / size, steps % size);
+        if n < 3 {
+            return Ok(self.walk(steps).at(steps));
+        }
+        if !self._start_lines_clear() {
+            return Err("start's row and column aren't clear".to_string());
         }
-        res
+
+        let reachable = self.walk(offset + 2 * size);
+        let samples = Differences::new([0, 1, 2].map(|i| reachable.at(offset + i * size)));
+        Ok(samples.extrapolate(n as i128) as u64)
+    }
+
+    fn _start_lines_clear(&self) -> bool {
+        let Some(rocks) = self.plots.get(&PlotType::Rock) else {
+            return true;
+        };
+        (0..=self.lower_right.x).all(|x| !rocks.contains(&Point::new(x, self.start.y)))
+            && (0..=self.lower_right.y).all(|y| !rocks.contains(&Point::new(self.start.x, y)))
+    }
+}
+
+impl StateSpace for Garden {
+    type State = Point;
+
+    fn successors(&self, p: &Point) -> Vec<(Point, u32)> {
+        Dir::iter()
+            .map(|d| *p + d)
+            .filter(|p| (self.is_valid)(self, p))
+            .map(|p| (p, 1))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let is_valid = |garden: &Garden, p: &Point| -> bool {
+            garden.plots.get(&PlotType::Open).unwrap().contains(p) || *p == garden.start
+        };
+        assert_eq!(_get_data("21.txt.test", is_valid).walk(6).at(6), 16);
+
+        let reachable = _get_data("21.txt.test", _is_valid_tiled).walk(500);
+        for (steps, expected) in [(6, 16), (10, 50), (50, 1594), (100, 6536), (500, 167004)] {
+            assert_eq!(reachable.at(steps), expected);
+        }
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        // the example's middle row and column aren't clear, so check against a garden
+        // shaped like the real input
+        let garden = _get_data("21.txt.test2", _is_valid_tiled);
+        let reachable = garden.walk(11 * 8 + 5);
+        for steps in [11 * 3 + 5, 11 * 4 + 5, 11 * 6 + 5, 11 * 8 + 5] {
+            assert_eq!(
+                garden.extrapolate(steps),
+                Ok(reachable.at(steps)),
+                "{steps}"
+            );
+        }
+
+        // the example's start row has rocks in it, so only short walks work
+        let garden = _get_data("21.txt.test", _is_valid_tiled);
+        assert_eq!(garden.extrapolate(10), Ok(50));
+        assert!(garden.extrapolate(100).is_err());
     }
 }
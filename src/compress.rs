@@ -0,0 +1,130 @@
+use itertools::Itertools;
+
+/// coordinate compression along a single axis
+///
+/// keeps the sorted, deduplicated set of "interesting" values so huge, sparse coordinates
+/// can be worked on by index and mapped back afterwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compressed {
+    values: Vec<i64>,
+}
+
+/// a run of original coordinates that maps onto a single compressed cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: i64,
+    pub len: i64,
+}
+
+impl Compressed {
+    pub fn new(values: impl IntoIterator<Item = i64>) -> Self {
+        Self {
+            values: values.into_iter().sorted_unstable().dedup().collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self) -> &[i64] {
+        &self.values
+    }
+
+    /// original value -> compressed index
+    pub fn index_of(&self, value: i64) -> Option<usize> {
+        self.values.binary_search(&value).ok()
+    }
+
+    /// compressed index -> original value
+    pub fn value(&self, idx: usize) -> i64 {
+        self.values[idx]
+    }
+
+    /// where each value ends up if every coordinate *not* in the set counts `factor` times
+    /// the first value stays put, so distances between values are all that's meaningful
+    ///
+    /// e.g. `[0, 3]` with factor 10: the 2 empty coords between them become 20 -> `[0, 21]`
+    pub fn expanded(&self, factor: i64) -> Vec<i64> {
+        let Some(&first) = self.values.first() else {
+            return vec![];
+        };
+        let mut res = vec![first];
+        for (a, b) in self.values.iter().tuple_windows() {
+            let empty = b - a - 1;
+            res.push(res.last().unwrap() + 1 + empty * factor);
+        }
+        res
+    }
+
+    /// split `min..=max` of the axis into alternating value/gap spans, dropping empty gaps
+    ///
+    /// each span becomes one cell of a compressed grid whose weight is its `len`, which is
+    /// what you want for flood fills or area sums over huge coordinates
+    pub fn spans(&self) -> Vec<Span> {
+        let mut res = vec![];
+        for (i, &v) in self.values.iter().enumerate() {
+            res.push(Span { start: v, len: 1 });
+            if let Some(&next) = self.values.get(i + 1) {
+                if next - v > 1 {
+                    res.push(Span {
+                        start: v + 1,
+                        len: next - v - 1,
+                    });
+                }
+            }
+        }
+        res
+    }
+}
+
+/// compress the x and y values of a set of points independently
+pub fn compress_xy(points: &[(i64, i64)]) -> (Compressed, Compressed) {
+    (
+        Compressed::new(points.iter().map(|p| p.0)),
+        Compressed::new(points.iter().map(|p| p.1)),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compress() {
+        let c = Compressed::new([100, -5, 7, 100, 7]);
+        assert_eq!(c.values(), &[-5, 7, 100]);
+        assert_eq!(c.index_of(7), Some(1));
+        assert_eq!(c.index_of(8), None);
+        assert_eq!(c.value(2), 100);
+    }
+
+    #[test]
+    fn test_expanded() {
+        let c = Compressed::new([0, 3, 4, 7]);
+        assert_eq!(c.expanded(1), vec![0, 3, 4, 7]);
+        assert_eq!(c.expanded(2), vec![0, 5, 6, 11]);
+        assert_eq!(c.expanded(10), vec![0, 21, 22, 43]);
+        assert_eq!(Compressed::new([]).expanded(2), vec![]);
+    }
+
+    #[test]
+    fn test_spans() {
+        let c = Compressed::new([0, 1, 5]);
+        assert_eq!(
+            c.spans(),
+            vec![
+                Span { start: 0, len: 1 },
+                Span { start: 1, len: 1 },
+                Span { start: 2, len: 3 },
+                Span { start: 5, len: 1 },
+            ]
+        );
+        let total: i64 = c.spans().iter().map(|s| s.len).sum();
+        assert_eq!(total, 6);
+    }
+}
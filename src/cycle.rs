@@ -0,0 +1,129 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// the sequence `x0, f(x0), f(f(x0)), ...` starts repeating at index `start` every `len` steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub len: usize,
+}
+
+impl Cycle {
+    /// the smallest index whose state is the same as the state at index `n`
+    pub fn equivalent(&self, n: usize) -> usize {
+        if n < self.start {
+            return n;
+        }
+        self.start + (n - self.start) % self.len
+    }
+}
+
+/// tortoise and hare. only needs `Eq`, keeps no history
+pub fn floyd<S: Eq + Clone>(init: S, step: impl Fn(&S) -> S) -> Cycle {
+    let mut tortoise = step(&init);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+
+    // the distance from init to the cycle start equals the distance from the meeting point
+    let mut start = 0;
+    tortoise = init;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    let mut len = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        len += 1;
+    }
+    Cycle { start, len }
+}
+
+/// teleporting tortoise. like `floyd` but fewer calls to `step`
+pub fn brent<S: Eq + Clone>(init: S, step: impl Fn(&S) -> S) -> Cycle {
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = init.clone();
+    let mut hare = step(&init);
+    while tortoise != hare {
+        if power == len {
+            tortoise = hare.clone();
+            power *= 2;
+            len = 0;
+        }
+        hare = step(&hare);
+        len += 1;
+    }
+
+    // walk a hare `len` ahead of the tortoise, they meet at the start
+    let mut start = 0;
+    tortoise = init.clone();
+    hare = (0..len).fold(init, |s, _| step(&s));
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, len }
+}
+
+/// remember every state seen. calls `step` the fewest times and returns the history,
+/// `history[i]` being the state after `i` steps, up to the first repeat
+pub fn find_cycle<S: Hash + Eq + Clone>(init: S, step: impl Fn(&S) -> S) -> (Cycle, Vec<S>) {
+    let mut seen = HashMap::new();
+    let mut history = vec![];
+    let mut cur = init;
+    loop {
+        if let Some(&start) = seen.get(&cur) {
+            let len = history.len() - start;
+            return (Cycle { start, len }, history);
+        }
+        seen.insert(cur.clone(), history.len());
+        let next = step(&cur);
+        history.push(cur);
+        cur = next;
+    }
+}
+
+/// the state after applying `step` `n` times, skipping over full cycles
+pub fn state_after<S: Hash + Eq + Clone>(init: S, step: impl Fn(&S) -> S, n: usize) -> S {
+    let (cycle, mut history) = find_cycle(init, step);
+    history.swap_remove(cycle.equivalent(n))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// 0, 1, 2, 3, 4, 5, 6, 3, 4, ...
+    fn step(v: &u32) -> u32 {
+        match v {
+            6 => 3,
+            _ => v + 1,
+        }
+    }
+
+    #[test]
+    fn test_cycle() {
+        let expected = Cycle { start: 3, len: 4 };
+        assert_eq!(floyd(0, step), expected);
+        assert_eq!(brent(0, step), expected);
+        assert_eq!(find_cycle(0, step).0, expected);
+        assert_eq!(find_cycle(0, step).1, vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(brent(3, step), Cycle { start: 0, len: 4 });
+        assert_eq!(floyd(5, |v| *v), Cycle { start: 0, len: 1 });
+    }
+
+    #[test]
+    fn test_state_after() {
+        assert_eq!(state_after(0, step, 2), 2);
+        assert_eq!(state_after(0, step, 7), 3);
+        assert_eq!(state_after(0, step, 1_000_000_000), 4);
+        assert_eq!(Cycle { start: 3, len: 4 }.equivalent(9), 5);
+    }
+}
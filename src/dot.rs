@@ -0,0 +1,135 @@
+use std::{fmt::Write, fs, path::PathBuf};
+
+use itertools::Itertools;
+
+use crate::utils::dot_path;
+
+/// minimal graphviz DOT builder for eyeballing puzzle graphs
+///
+/// render with e.g. `dot -Tsvg dot/2023/20.dot -o 20.svg`
+#[derive(Debug, Clone, Default)]
+pub struct Dot {
+    name: String,
+    nodes: Vec<(String, Vec<(String, String)>)>,
+    edges: Vec<(String, String, Option<String>)>,
+}
+
+/// anything that can be drawn as a graph
+pub trait ToDot {
+    fn to_dot(&self) -> Dot;
+
+    /// write to the dot directory, returning where it went
+    fn write_dot(&self, fname: &str, year: u16) -> std::io::Result<PathBuf> {
+        self.to_dot().write(fname, year)
+    }
+}
+
+impl Dot {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// declare a node with attributes, e.g. `[("shape", "box")]`
+    /// nodes only referenced by edges don't need declaring
+    pub fn node(&mut self, id: impl Into<String>, attrs: &[(&str, &str)]) -> &mut Self {
+        let attrs = attrs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        self.nodes.push((id.into(), attrs));
+        self
+    }
+
+    pub fn edge(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        label: Option<String>,
+    ) -> &mut Self {
+        self.edges.push((from.into(), to.into(), label));
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut res = String::new();
+        writeln!(res, "digraph {} {{", _quote(&self.name)).unwrap();
+        for (id, attrs) in &self.nodes {
+            write!(res, "    {}", _quote(id)).unwrap();
+            if !attrs.is_empty() {
+                let attrs = attrs
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, _quote(v)))
+                    .join(", ");
+                write!(res, " [{}]", attrs).unwrap();
+            }
+            writeln!(res, ";").unwrap();
+        }
+        for (from, to, label) in &self.edges {
+            write!(res, "    {} -> {}", _quote(from), _quote(to)).unwrap();
+            if let Some(label) = label {
+                write!(res, " [label={}]", _quote(label)).unwrap();
+            }
+            writeln!(res, ";").unwrap();
+        }
+        res.push('}');
+        res.push('\n');
+        res
+    }
+
+    pub fn write(&self, fname: &str, year: u16) -> std::io::Result<PathBuf> {
+        let path = dot_path(fname, year);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, self.render())?;
+        Ok(path)
+    }
+}
+
+fn _quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut dot = Dot::new("g");
+        dot.node("a", &[("shape", "box")])
+            .node("b", &[])
+            .edge("a", "b", Some("3".to_string()))
+            .edge("b", "a\"", None);
+        let expected = r#"digraph "g" {
+    "a" [shape="box"];
+    "b";
+    "a" -> "b" [label="3"];
+    "b" -> "a\"";
+}
+"#;
+        assert_eq!(dot.render(), expected);
+    }
+
+    #[test]
+    fn test_write() {
+        struct Pair;
+        impl ToDot for Pair {
+            fn to_dot(&self) -> Dot {
+                let mut dot = Dot::new("pair");
+                dot.edge("a", "b", None);
+                dot
+            }
+        }
+
+        let path = Pair.write_dot("test_write.dot", 0).unwrap();
+        assert_eq!(path, dot_path("test_write.dot", 0));
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, Pair.to_dot().render());
+        assert!(written.contains("\"a\" -> \"b\";"));
+    }
+}
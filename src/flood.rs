@@ -0,0 +1,162 @@
+use crate::grid::Grid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// north/east/south/west
+    Four,
+    /// `Four` plus diagonals
+    Eight,
+}
+
+/// a connected group of passable cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub cells: Vec<(usize, usize)>,
+    /// any cell sits on the outer edge of the grid
+    pub touches_border: bool,
+    /// number of cell sides that face something not in the region, grid edge included
+    pub perimeter: usize,
+}
+
+#[derive(Debug)]
+pub struct Regions {
+    /// index into `regions` for every passable cell, `None` for impassable ones
+    pub labels: Grid<Option<usize>>,
+    pub regions: Vec<Region>,
+}
+
+const OFFSETS_4: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const OFFSETS_8: [(i64, i64); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+];
+
+impl Connectivity {
+    fn offsets(&self) -> &'static [(i64, i64)] {
+        match self {
+            Connectivity::Four => &OFFSETS_4,
+            Connectivity::Eight => &OFFSETS_8,
+        }
+    }
+}
+
+impl Region {
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+impl Regions {
+    pub fn region_at(&self, x: usize, y: usize) -> Option<&Region> {
+        self.labels
+            .get(x, y)
+            .copied()
+            .flatten()
+            .map(|i| &self.regions[i])
+    }
+
+    /// regions completely surrounded by impassable cells
+    pub fn enclosed(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter().filter(|r| !r.touches_border)
+    }
+}
+
+/// flood fill every passable cell of `grid`, labeling each connected region
+///
+/// perimeter is always counted on the 4 sides of a cell, regardless of `connectivity`
+pub fn label_regions<T>(
+    grid: &Grid<T>,
+    passable: impl Fn(&T) -> bool,
+    connectivity: Connectivity,
+) -> Regions {
+    let (width, height) = (grid.width(), grid.height());
+    let mut labels = Grid::new(width, height, vec![None; width * height]);
+    let mut regions = vec![];
+
+    let neighbor = |x: usize, y: usize, (dx, dy): (i64, i64)| -> Option<(usize, usize)> {
+        let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+        if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+            return None;
+        }
+        Some((nx as usize, ny as usize))
+    };
+
+    for ((x, y), v) in grid.iter() {
+        if labels[(x, y)].is_some() || !passable(v) {
+            continue;
+        }
+
+        let id = regions.len();
+        let mut region = Region {
+            cells: vec![],
+            touches_border: false,
+            perimeter: 0,
+        };
+        labels[(x, y)] = Some(id);
+        let mut stack = vec![(x, y)];
+        while let Some((cx, cy)) = stack.pop() {
+            region.cells.push((cx, cy));
+            region.touches_border |= cx == 0 || cy == 0 || cx == width - 1 || cy == height - 1;
+            region.perimeter += OFFSETS_4
+                .iter()
+                .filter(|o| {
+                    !neighbor(cx, cy, **o).is_some_and(|(nx, ny)| passable(&grid[(nx, ny)]))
+                })
+                .count();
+
+            for o in connectivity.offsets() {
+                let Some((nx, ny)) = neighbor(cx, cy, *o) else {
+                    continue;
+                };
+                if labels[(nx, ny)].is_none() && passable(&grid[(nx, ny)]) {
+                    labels[(nx, ny)] = Some(id);
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        regions.push(region);
+    }
+
+    Regions { labels, regions }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_regions() {
+        let grid = Grid::from_str("..#..\n.#.#.\n..#..", |c| c);
+        let regions = label_regions(&grid, |c| *c == '.', Connectivity::Four);
+        assert_eq!(regions.regions.len(), 3);
+        let enclosed = regions.enclosed().collect::<Vec<_>>();
+        assert_eq!(enclosed.len(), 1);
+        let middle = regions.region_at(2, 1).unwrap();
+        assert_eq!(middle.size(), 1);
+        assert_eq!(middle.perimeter, 4);
+        assert_eq!(regions.region_at(0, 0).unwrap().size(), 5);
+        assert!(regions.region_at(2, 0).is_none());
+
+        let regions = label_regions(&grid, |c| *c == '#', Connectivity::Eight);
+        assert_eq!(regions.regions.len(), 1);
+        assert_eq!(regions.regions[0].perimeter, 16);
+        let regions = label_regions(&grid, |c| *c == '#', Connectivity::Four);
+        assert_eq!(regions.regions.len(), 4);
+    }
+
+    #[test]
+    fn test_enclosed() {
+        let grid = Grid::from_str("#####\n#..##\n#####", |c| c);
+        let regions = label_regions(&grid, |c| *c == '.', Connectivity::Four);
+        let enclosed = regions.enclosed().collect::<Vec<_>>();
+        assert_eq!(enclosed.len(), 1);
+        assert_eq!(enclosed[0].size(), 2);
+        assert_eq!(enclosed[0].perimeter, 6);
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    dot::{Dot, ToDot},
+    utils::Memo,
+};
+
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub weight: u64,
+}
+
+/// directed multigraph with weighted edges and string node names
+///
+/// names are interned to `NodeId`s so the rest of the graph works on plain indexes.
+/// both outgoing and incoming edges are tracked so reverse lookups are cheap
+#[derive(Debug, Default, Clone)]
+pub struct DiGraph {
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    edges: Vec<Edge>,
+    /// edge indexes leaving each node
+    outgoing: Vec<Vec<usize>>,
+    /// edge indexes entering each node
+    incoming: Vec<Vec<usize>>,
+}
+
+impl DiGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// id for `name`, adding the node if it's new
+    pub fn intern(&mut self, name: &str) -> NodeId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.outgoing.push(vec![]);
+        self.incoming.push(vec![]);
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn add_edge(&mut self, from: &str, to: &str, weight: u64) {
+        let (from, to) = (self.intern(from), self.intern(to));
+        self.outgoing[from].push(self.edges.len());
+        self.incoming[to].push(self.edges.len());
+        self.edges.push(Edge { from, to, weight });
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        0..self.names.len()
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    pub fn out_edges(&self, id: NodeId) -> impl Iterator<Item = &Edge> {
+        self.outgoing[id].iter().map(|i| &self.edges[*i])
+    }
+
+    pub fn in_edges(&self, id: NodeId) -> impl Iterator<Item = &Edge> {
+        self.incoming[id].iter().map(|i| &self.edges[*i])
+    }
+
+    /// kahn's algorithm. `None` if there's a cycle
+    pub fn topo_order(&self) -> Option<Vec<NodeId>> {
+        let mut in_degree = self.incoming.iter().map(Vec::len).collect::<Vec<_>>();
+        let mut queue = self
+            .nodes()
+            .filter(|id| in_degree[*id] == 0)
+            .collect::<VecDeque<_>>();
+        let mut res = vec![];
+        while let Some(id) = queue.pop_front() {
+            res.push(id);
+            for e in self.out_edges(id) {
+                in_degree[e.to] -= 1;
+                if in_degree[e.to] == 0 {
+                    queue.push_back(e.to);
+                }
+            }
+        }
+        (res.len() == self.len()).then_some(res)
+    }
+
+    /// every node reachable by following edges out of `id`, not including `id` itself
+    /// (unless it's on a cycle)
+    pub fn descendants(&self, id: NodeId) -> HashSet<NodeId> {
+        self._reach(id, |id| self.out_edges(id).map(|e| e.to).collect())
+    }
+
+    /// every node that can reach `id`
+    pub fn ancestors(&self, id: NodeId) -> HashSet<NodeId> {
+        self._reach(id, |id| self.in_edges(id).map(|e| e.from).collect())
+    }
+
+    /// sum, over every path leaving `id`, of the product of the weights along it
+    /// i.e. "how many bags are inside this bag". graph must be acyclic
+    pub fn weighted_descendants(&self, id: NodeId) -> u64 {
+        self._weighted_descendants(id, &mut Memo::new())
+    }
+
+    /// sum, over every path from `from` to `to`, of the product of the weights along it
+    /// graph must be acyclic
+    pub fn path_product_sum(&self, from: NodeId, to: NodeId) -> u64 {
+        self._path_product_sum(from, to, &mut Memo::new())
+    }
+
+    /// strongly connected components, each listed after every component it can reach
+    pub fn sccs(&self) -> Vec<Vec<NodeId>> {
+        let adj = self
+            .nodes()
+            .map(|id| self.out_edges(id).map(|e| e.to).collect())
+            .collect::<Vec<_>>();
+        strongly_connected(&adj)
+    }
+
+    fn _weighted_descendants(&self, id: NodeId, memo: &mut Memo<NodeId, u64>) -> u64 {
+        memo.get_or_insert_with(id, |memo| {
+            self.out_edges(id)
+                .map(|e| e.weight * (1 + self._weighted_descendants(e.to, memo)))
+                .sum()
+        })
+    }
+
+    fn _path_product_sum(&self, from: NodeId, to: NodeId, memo: &mut Memo<NodeId, u64>) -> u64 {
+        if from == to {
+            return 1;
+        }
+        memo.get_or_insert_with(from, |memo| {
+            self.out_edges(from)
+                .map(|e| e.weight * self._path_product_sum(e.to, to, memo))
+                .sum()
+        })
+    }
+
+    fn _reach(&self, id: NodeId, next: impl Fn(NodeId) -> Vec<NodeId>) -> HashSet<NodeId> {
+        let mut res = HashSet::new();
+        let mut stack = next(id);
+        while let Some(cur) = stack.pop() {
+            if res.insert(cur) {
+                stack.extend(next(cur));
+            }
+        }
+        res
+    }
+}
+
+/// tarjan's algorithm over an adjacency list, without recursion
+///
+/// components come out in reverse topological order: each one is listed after every
+/// component it can reach, so results can be built up from the front
+pub fn strongly_connected(adj: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNSEEN: usize = usize::MAX;
+    let n = adj.len();
+    let mut index = vec![UNSEEN; n];
+    let mut low = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = vec![];
+    let mut res = vec![];
+    let mut next_index = 0;
+
+    for root in 0..n {
+        if index[root] != UNSEEN {
+            continue;
+        }
+        // (node, how many of its edges have been looked at)
+        let mut work = vec![(root, 0)];
+        while let Some((v, i)) = work.pop() {
+            if i == 0 {
+                index[v] = next_index;
+                low[v] = next_index;
+                next_index += 1;
+                stack.push(v);
+                on_stack[v] = true;
+            }
+            if let Some(&w) = adj[v].get(i) {
+                work.push((v, i + 1));
+                if index[w] == UNSEEN {
+                    work.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+
+            // done with v
+            if low[v] == index[v] {
+                let mut component = vec![];
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                res.push(component);
+            }
+            if let Some((parent, _)) = work.last() {
+                low[*parent] = low[*parent].min(low[v]);
+            }
+        }
+    }
+    res
+}
+
+/// edges are labeled with their weight
+impl ToDot for DiGraph {
+    fn to_dot(&self) -> Dot {
+        let mut dot = Dot::new("graph");
+        for id in self.nodes() {
+            dot.node(self.name(id), &[]);
+        }
+        for e in &self.edges {
+            dot.edge(self.name(e.from), self.name(e.to), Some(e.weight.to_string()));
+        }
+        dot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn _graph() -> DiGraph {
+        // a -2-> b -3-> d
+        // a -1-> c -1-> d
+        //        c -4-> e
+        let mut g = DiGraph::new();
+        g.add_edge("a", "b", 2);
+        g.add_edge("a", "c", 1);
+        g.add_edge("b", "d", 3);
+        g.add_edge("c", "d", 1);
+        g.add_edge("c", "e", 4);
+        g
+    }
+
+    #[test]
+    fn test_interning() {
+        let mut g = _graph();
+        assert_eq!(g.len(), 5);
+        assert_eq!(g.intern("d"), g.id("d").unwrap());
+        assert_eq!(g.len(), 5);
+        assert_eq!(g.name(g.id("e").unwrap()), "e");
+        assert_eq!(g.in_edges(g.id("d").unwrap()).count(), 2);
+    }
+
+    #[test]
+    fn test_reachability() {
+        let g = _graph();
+        let names = |ids: HashSet<NodeId>| {
+            let mut v = ids.iter().map(|i| g.name(*i)).collect::<Vec<_>>();
+            v.sort();
+            v
+        };
+        assert_eq!(names(g.descendants(g.id("c").unwrap())), vec!["d", "e"]);
+        assert_eq!(names(g.ancestors(g.id("d").unwrap())), vec!["a", "b", "c"]);
+        assert!(g.ancestors(g.id("a").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_topo() {
+        let mut g = _graph();
+        let order = g.topo_order().unwrap();
+        let pos = |name| order.iter().position(|i| *i == g.id(name).unwrap());
+        assert!(pos("a") < pos("b") && pos("b") < pos("d") && pos("c") < pos("e"));
+
+        g.add_edge("d", "a", 1);
+        assert_eq!(g.topo_order(), None);
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = _graph().to_dot().render();
+        assert!(dot.contains("\"a\" -> \"b\" [label=\"2\"];"));
+        assert!(dot.contains("    \"e\";"));
+    }
+
+    #[test]
+    fn test_sccs() {
+        let mut g = _graph();
+        g.add_edge("d", "b", 1);
+        g.add_edge("e", "e", 1);
+        let sccs = g
+            .sccs()
+            .iter()
+            .map(|c| {
+                let mut names = c.iter().map(|i| g.name(*i)).collect::<Vec<_>>();
+                names.sort();
+                names
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sccs.len(), 4);
+        let pos = |name| sccs.iter().position(|c| c.contains(&name)).unwrap();
+        assert_eq!(sccs[pos("b")], vec!["b", "d"]);
+        // everything reachable from a component comes before it
+        assert!(pos("b") < pos("c") && pos("e") < pos("c") && pos("c") < pos("a"));
+    }
+
+    #[test]
+    fn test_weights() {
+        let g = _graph();
+        let a = g.id("a").unwrap();
+        // b: 2 * (1 + 3) = 8, c: 1 * (1 + 1 + 4) = 6
+        assert_eq!(g.weighted_descendants(a), 14);
+        // a->b->d: 6, a->c->d: 1
+        assert_eq!(g.path_product_sum(a, g.id("d").unwrap()), 7);
+        assert_eq!(
+            g.path_product_sum(g.id("b").unwrap(), g.id("e").unwrap()),
+            0
+        );
+    }
+}
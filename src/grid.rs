@@ -0,0 +1,435 @@
+use std::{
+    fmt::Display,
+    ops::{Index, IndexMut},
+};
+
+use itertools::Itertools;
+
+use crate::point::Dir;
+
+/// dense, row-major 2d grid
+///
+/// rotations, transposes and mirrors are done through `GridView`/`GridViewMut`,
+/// which only remap coordinates and never copy the underlying data
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    data: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+/// maps view coordinates onto grid coordinates
+/// a view coordinate is first (optionally) swapped, then each grid axis is (optionally) flipped
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+struct Transform {
+    swap: bool,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GridView<'a, T> {
+    grid: &'a Grid<T>,
+    transform: Transform,
+}
+
+#[derive(Debug)]
+pub struct GridViewMut<'a, T> {
+    grid: &'a mut Grid<T>,
+    transform: Transform,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, data: Vec<T>) -> Self {
+        assert_eq!(
+            width * height,
+            data.len(),
+            "data does not fit {width}x{height}"
+        );
+        Self {
+            data,
+            width,
+            height,
+        }
+    }
+
+    /// parse a newline separated block of text, converting each char with `f`
+    pub fn from_str(s: impl AsRef<str>, f: impl Fn(char) -> T) -> Self {
+        let lines = s.as_ref().lines().filter(|l| !l.is_empty()).collect_vec();
+        let height = lines.len();
+        let width = lines.first().map_or(0, |l| l.chars().count());
+        let data = lines.iter().flat_map(|l| l.chars().map(&f)).collect();
+        Self::new(width, height, data)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self._idx(x, y).map(|i| &self.data[i])
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self._idx(x, y).map(|i| &mut self.data[i])
+    }
+
+    /// all cells in row-major order along with their (x, y)
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(i, v)| ((i % width, i / width), v))
+    }
+
+    pub fn view(&self) -> GridView<'_, T> {
+        GridView {
+            grid: self,
+            transform: Transform::default(),
+        }
+    }
+
+    pub fn view_mut(&mut self) -> GridViewMut<'_, T> {
+        GridViewMut {
+            grid: self,
+            transform: Transform::default(),
+        }
+    }
+
+    fn _idx(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        self.get(x, y).expect("in bounds")
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        self.get_mut(x, y).expect("in bounds")
+    }
+}
+
+impl Transform {
+    /// (width, height) as seen through the transform
+    fn dims(&self, width: usize, height: usize) -> (usize, usize) {
+        match self.swap {
+            true => (height, width),
+            false => (width, height),
+        }
+    }
+
+    /// convert view (x, y) to grid (x, y)
+    fn apply(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        let (mut x, mut y) = match self.swap {
+            true => (y, x),
+            false => (x, y),
+        };
+        if self.flip_x {
+            x = width - 1 - x;
+        }
+        if self.flip_y {
+            y = height - 1 - y;
+        }
+        (x, y)
+    }
+
+    fn transposed(mut self) -> Self {
+        self.swap = !self.swap;
+        self
+    }
+
+    /// flip left <-> right as seen through the view
+    fn mirrored_x(mut self) -> Self {
+        match self.swap {
+            true => self.flip_y = !self.flip_y,
+            false => self.flip_x = !self.flip_x,
+        }
+        self
+    }
+
+    /// flip top <-> bottom as seen through the view
+    fn mirrored_y(mut self) -> Self {
+        match self.swap {
+            true => self.flip_x = !self.flip_x,
+            false => self.flip_y = !self.flip_y,
+        }
+        self
+    }
+
+    /// rotate clockwise by 90° `num_rotations` times. negative is counter-clockwise
+    fn rotated(self, num_rotations: i32) -> Self {
+        (0..num_rotations.rem_euclid(4)).fold(self, |t, _| t.mirrored_y().transposed())
+    }
+
+    /// rotate so that the `dir` edge of the grid ends up on top
+    fn oriented(self, dir: Dir) -> Self {
+        self.rotated(match dir {
+            Dir::North => 0,
+            Dir::East => -1,
+            Dir::South => 2,
+            Dir::West => 1,
+        })
+    }
+}
+
+/// transforms shared by both view types
+macro_rules! view_transforms {
+    () => {
+        pub fn transposed(self) -> Self {
+            Self {
+                transform: self.transform.transposed(),
+                ..self
+            }
+        }
+
+        /// flip left <-> right
+        pub fn mirrored_x(self) -> Self {
+            Self {
+                transform: self.transform.mirrored_x(),
+                ..self
+            }
+        }
+
+        /// flip top <-> bottom
+        pub fn mirrored_y(self) -> Self {
+            Self {
+                transform: self.transform.mirrored_y(),
+                ..self
+            }
+        }
+
+        /// rotate clockwise by 90° `num_rotations` times. negative is counter-clockwise
+        pub fn rotated(self, num_rotations: i32) -> Self {
+            Self {
+                transform: self.transform.rotated(num_rotations),
+                ..self
+            }
+        }
+
+        /// rotate so that the `dir` edge ends up on top, i.e. "north" in the view is `dir`
+        /// in the grid. lets you write logic for north once and reuse it for every direction
+        pub fn oriented(self, dir: Dir) -> Self {
+            Self {
+                transform: self.transform.oriented(dir),
+                ..self
+            }
+        }
+
+        pub fn width(&self) -> usize {
+            self.transform.dims(self.grid.width, self.grid.height).0
+        }
+
+        pub fn height(&self) -> usize {
+            self.transform.dims(self.grid.width, self.grid.height).1
+        }
+
+        pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+            let (x, y) = self._to_grid(x, y)?;
+            self.grid.get(x, y)
+        }
+
+        fn _to_grid(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+            if x >= self.width() || y >= self.height() {
+                return None;
+            }
+            Some(
+                self.transform
+                    .apply(x, y, self.grid.width, self.grid.height),
+            )
+        }
+    };
+}
+
+impl<'a, T> GridView<'a, T> {
+    view_transforms!();
+
+    pub fn row(&self, y: usize) -> impl Iterator<Item = &'a T> + '_ {
+        (0..self.width()).map(move |x| {
+            let (x, y) = self._to_grid(x, y).expect("in bounds");
+            &self.grid[(x, y)]
+        })
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = &'a T> + '_> + '_ {
+        (0..self.height()).map(|y| self.row(y))
+    }
+
+    /// copy the view out into a new grid
+    pub fn to_grid(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let data = self.rows().flatten().cloned().collect();
+        Grid::new(self.width(), self.height(), data)
+    }
+
+    /// number of cells that differ between rows `y1` and `y2`
+    pub fn row_diff(&self, y1: usize, y2: usize) -> usize
+    where
+        T: PartialEq,
+    {
+        self.row(y1)
+            .zip(self.row(y2))
+            .filter(|(a, b)| a != b)
+            .count()
+    }
+
+    /// find horizontal lines of reflection: the returned value is the number of rows
+    /// above the line. `smudges` is the exact number of cells that must differ across the line
+    ///
+    /// for vertical lines, call this on `transposed()`
+    pub fn reflections(&self, smudges: usize) -> Vec<usize>
+    where
+        T: PartialEq,
+    {
+        (1..self.height())
+            .filter(|&above| {
+                let num_pairs = above.min(self.height() - above);
+                let diffs: usize = (0..num_pairs)
+                    .map(|i| self.row_diff(above - 1 - i, above + i))
+                    .sum();
+                diffs == smudges
+            })
+            .collect()
+    }
+}
+
+impl<'a, T> GridViewMut<'a, T> {
+    view_transforms!();
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        let (x, y) = self._to_grid(x, y)?;
+        self.grid.get_mut(x, y)
+    }
+
+    pub fn swap(&mut self, (x1, y1): (usize, usize), (x2, y2): (usize, usize)) {
+        let (x1, y1) = self._to_grid(x1, y1).expect("in bounds");
+        let (x2, y2) = self._to_grid(x2, y2).expect("in bounds");
+        let (i1, i2) = (y1 * self.grid.width + x1, y2 * self.grid.width + x2);
+        self.grid.data.swap(i1, i2);
+    }
+
+    /// roll every `rolls` cell as far north as it goes in the view
+    /// cells that are `blocks` stay put. everything else is treated as empty space
+    ///
+    /// combine with `oriented` to tilt in any direction
+    pub fn tilt_north(&mut self, rolls: impl Fn(&T) -> bool, blocks: impl Fn(&T) -> bool) {
+        for x in 0..self.width() {
+            let mut dest = 0;
+            for y in 0..self.height() {
+                let cell = self.get(x, y).expect("in bounds");
+                if blocks(cell) {
+                    dest = y + 1;
+                } else if rolls(cell) {
+                    self.swap((x, dest), (x, y));
+                    dest += 1;
+                }
+            }
+        }
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = self
+            .data
+            .chunks(self.width.max(1))
+            .map(|row| row.iter().map(|v| v.to_string()).join(""))
+            .join("\n");
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn _grid() -> Grid<char> {
+        // abc
+        // def
+        Grid::from_str("abc\ndef", |c| c)
+    }
+
+    fn _to_string(view: GridView<char>) -> String {
+        view.rows().map(|r| r.collect::<String>()).join("\n")
+    }
+
+    #[test]
+    fn test_transforms() {
+        let g = _grid();
+        assert_eq!(_to_string(g.view()), "abc\ndef");
+        assert_eq!(_to_string(g.view().transposed()), "ad\nbe\ncf");
+        assert_eq!(_to_string(g.view().mirrored_x()), "cba\nfed");
+        assert_eq!(_to_string(g.view().mirrored_y()), "def\nabc");
+        assert_eq!(_to_string(g.view().rotated(1)), "da\neb\nfc");
+        assert_eq!(_to_string(g.view().rotated(2)), "fed\ncba");
+        assert_eq!(_to_string(g.view().rotated(-1)), "cf\nbe\nad");
+        assert_eq!(_to_string(g.view().rotated(4)), "abc\ndef");
+        assert_eq!(_to_string(g.view().rotated(1).rotated(-1)), "abc\ndef");
+        assert_eq!(_to_string(g.view().transposed().transposed()), "abc\ndef");
+        assert_eq!(g.view().rotated(1).width(), 2);
+        assert_eq!(g.view().rotated(1).get(1, 2), Some(&'c'));
+        assert_eq!(g.view().rotated(1).get(2, 0), None);
+    }
+
+    #[test]
+    fn test_oriented() {
+        let g = _grid();
+        // the `dir` edge ends up as the top row
+        assert_eq!(
+            _to_string(g.view().oriented(Dir::North)).lines().next(),
+            Some("abc")
+        );
+        assert_eq!(
+            _to_string(g.view().oriented(Dir::East)).lines().next(),
+            Some("cf")
+        );
+        assert_eq!(
+            _to_string(g.view().oriented(Dir::South)).lines().next(),
+            Some("fed")
+        );
+        assert_eq!(
+            _to_string(g.view().oriented(Dir::West)).lines().next(),
+            Some("da")
+        );
+    }
+
+    #[test]
+    fn test_tilt() {
+        let mut g = Grid::from_str("..O\nO#.\n.OO", |c| c);
+        g.view_mut().tilt_north(|c| *c == 'O', |c| *c == '#');
+        assert_eq!(g.to_string(), "O.O\n.#O\n.O.");
+
+        let mut g = Grid::from_str("O.O#.O", |c| c);
+        g.view_mut()
+            .oriented(Dir::East)
+            .tilt_north(|c| *c == 'O', |c| *c == '#');
+        assert_eq!(g.to_string(), ".OO#.O");
+        g.view_mut()
+            .oriented(Dir::West)
+            .tilt_north(|c| *c == 'O', |c| *c == '#');
+        assert_eq!(g.to_string(), "OO.#O.");
+    }
+
+    #[test]
+    fn test_reflections() {
+        let g = Grid::from_str(
+            "#.##..##.\n..#.##.#.\n##......#\n##......#\n..#.##.#.\n..##..##.\n#.#.##.#.",
+            |c| c,
+        );
+        assert_eq!(g.view().reflections(0), vec![]);
+        assert_eq!(g.view().transposed().reflections(0), vec![5]);
+        assert_eq!(g.view().reflections(1), vec![3]);
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    fmt::Debug,
+    ops::{Bound, RangeBounds, RangeInclusive},
+};
+
+use num_traits::PrimInt;
+
+/// a set of integers stored as sorted, disjoint, non-touching inclusive `(start, last)` pairs
+///
+/// build it from any mix of `a..b` and `a..=b`. every operation keeps the storage
+/// coalesced, so two sets holding the same values always compare equal. being inclusive,
+/// any run of values fits, right up to `T::MAX`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<(T, T)>,
+}
+
+impl<T: PrimInt + Debug> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { intervals: vec![] }
+    }
+
+    pub fn from_range(range: impl RangeBounds<T>) -> Self {
+        let mut res = Self::new();
+        res.insert(range);
+        res
+    }
+
+    /// merges with whatever it overlaps or touches, found by binary search
+    pub fn insert(&mut self, range: impl RangeBounds<T>) {
+        let Some((mut start, mut last)) = _bounds(&range) else {
+            return;
+        };
+        // intervals[lo..hi] overlap or touch the new one
+        let lo = self
+            .intervals
+            .partition_point(|(_, l)| _next(*l).is_some_and(|n| n < start));
+        let hi = self
+            .intervals
+            .partition_point(|(s, _)| _next(last).is_none_or(|n| *s <= n));
+        if lo < hi {
+            start = start.min(self.intervals[lo].0);
+            last = last.max(self.intervals[hi - 1].1);
+        }
+        self.intervals.splice(lo..hi, [(start, last)]);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// how many values are in the set. only `None` when that's more than a `u128` holds,
+    /// which takes the whole range of a 128-bit type
+    pub fn len(&self) -> Option<u128> {
+        self.intervals.iter().try_fold(0u128, |acc, (start, last)| {
+            acc.checked_add(_width(*start, *last)?)
+        })
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.intervals.partition_point(|(_, last)| *last < value);
+        self.intervals
+            .get(i)
+            .is_some_and(|(start, _)| *start <= value)
+    }
+
+    /// every value in `other` is in here too
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.intervals.first().map(|(start, _)| *start)
+    }
+
+    /// the intervals, in order
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.intervals.iter().map(|(start, last)| *start..=*last)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut all = self
+            .intervals
+            .iter()
+            .chain(&other.intervals)
+            .copied()
+            .collect::<Vec<_>>();
+        all.sort();
+        let mut intervals: Vec<(T, T)> = vec![];
+        for (start, last) in all {
+            match intervals.last_mut() {
+                Some(prev) if _next(prev.1).is_none_or(|n| start <= n) => prev.1 = prev.1.max(last),
+                _ => intervals.push((start, last)),
+            }
+        }
+        Self { intervals }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let (mut i, mut j) = (0, 0);
+        let mut intervals = vec![];
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (self.intervals[i], other.intervals[j]);
+            let (start, last) = (a.0.max(b.0), a.1.min(b.1));
+            if start <= last {
+                intervals.push((start, last));
+            }
+            match a.1 < b.1 {
+                true => i += 1,
+                false => j += 1,
+            }
+        }
+        Self { intervals }
+    }
+
+    /// values in here that aren't in `other`
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut intervals = vec![];
+        let mut j = 0;
+        for &(start, last) in &self.intervals {
+            // skip everything in `other` that ends before this starts
+            while j < other.intervals.len() && other.intervals[j].1 < start {
+                j += 1;
+            }
+            // where the part not yet covered by `other` starts, `None` once nothing's left
+            let mut rest = Some(start);
+            for &(o_start, o_last) in other.intervals[j..].iter().take_while(|o| o.0 <= last) {
+                let Some(s) = rest else {
+                    break;
+                };
+                if s < o_start {
+                    intervals.push((s, o_start - T::one()));
+                }
+                rest = _next(o_last).map(|n| n.max(s));
+            }
+            if let Some(s) = rest.filter(|s| *s <= last) {
+                intervals.push((s, last));
+            }
+        }
+        Self { intervals }
+    }
+
+    /// `(values < at, values >= at)`
+    pub fn split_at(&self, at: T) -> (Self, Self) {
+        let mut below = vec![];
+        let mut above = vec![];
+        for &(start, last) in &self.intervals {
+            if last < at {
+                below.push((start, last));
+            } else if start >= at {
+                above.push((start, last));
+            } else {
+                below.push((start, at - T::one()));
+                above.push((at, last));
+            }
+        }
+        (Self { intervals: below }, Self { intervals: above })
+    }
+}
+
+impl<T: PrimInt + Debug> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PrimInt + Debug, R: RangeBounds<T>> FromIterator<R> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        let mut res = Self::new();
+        for r in iter {
+            res.insert(r);
+        }
+        res
+    }
+}
+
+fn _next<T: PrimInt>(v: T) -> Option<T> {
+    v.checked_add(&T::one())
+}
+
+/// inclusive `(start, last)` for any bounded range, `None` if it's empty
+fn _bounds<T: PrimInt + Debug>(range: &impl RangeBounds<T>) -> Option<(T, T)> {
+    let start = match range.start_bound() {
+        Bound::Included(s) => *s,
+        Bound::Excluded(s) => _next(*s)?,
+        Bound::Unbounded => panic!("interval sets need a lower bound"),
+    };
+    let last = match range.end_bound() {
+        Bound::Included(e) => *e,
+        Bound::Excluded(e) => e.checked_sub(&T::one())?,
+        Bound::Unbounded => panic!("interval sets need an upper bound"),
+    };
+    (start <= last).then_some((start, last))
+}
+
+/// how many values in `start..=last`. `last - start` can overflow `T` when it's signed, so
+/// it's worked out in 128 bits
+fn _width<T: PrimInt>(start: T, last: T) -> Option<u128> {
+    let diff = match (start.to_i128(), last.to_i128()) {
+        (Some(s), Some(l)) => l.abs_diff(s),
+        _ => last.to_u128()? - start.to_u128()?,
+    };
+    diff.checked_add(1)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_basics() {
+        let mut s = IntervalSet::from_range(1..=3);
+        s.insert(4..6);
+        s.insert(10..12);
+        s.insert(7..7);
+        assert_eq!(s.iter().collect::<Vec<_>>(), vec![1..=5, 10..=11]);
+        assert_eq!(s.len(), Some(7));
+        assert!(s.contains(5) && !s.contains(6) && s.contains(10) && !s.contains(0));
+
+        let (lo, hi) = s.split_at(4);
+        assert_eq!(lo, IntervalSet::from_range(1..4));
+        assert_eq!(hi.iter().collect::<Vec<_>>(), vec![4..=5, 10..=11]);
+
+        let other = IntervalSet::from_range(3..=10);
+        assert_eq!(
+            s.difference(&other).iter().collect::<Vec<_>>(),
+            vec![1..=2, 11..=11]
+        );
+        assert_eq!(
+            s.intersection(&other).iter().collect::<Vec<_>>(),
+            vec![3..=5, 10..=10]
+        );
+        assert!(s.is_superset(&IntervalSet::from_range(2..5)));
+        assert!(!s.is_superset(&other));
+        assert!(s.overlaps(&other));
+    }
+
+    #[test]
+    fn test_max() {
+        let mut s = IntervalSet::from_range(250u8..=u8::MAX);
+        assert_eq!(s.iter().collect::<Vec<_>>(), vec![250..=255]);
+        assert!(s.contains(254) && s.contains(u8::MAX));
+        assert_eq!(s.len(), Some(6));
+        s.insert(240..250);
+        assert_eq!(s, IntervalSet::from_range(240..=u8::MAX));
+
+        let all = IntervalSet::from_range(0..=u8::MAX);
+        assert_eq!(all.difference(&s), IntervalSet::from_range(0..240));
+        assert!(s.difference(&all).is_empty());
+        assert_eq!(all.intersection(&s), s);
+        assert_eq!(s.union(&IntervalSet::from_range(0..240)), all);
+        let (lo, hi) = all.split_at(u8::MAX);
+        assert_eq!((lo.len(), hi.len()), (Some(255), Some(1)));
+
+        assert!(
+            IntervalSet::from_range((Bound::Excluded(u8::MAX), Bound::Included(u8::MAX)))
+                .is_empty()
+        );
+        assert!(IntervalSet::from_range(0..0u8).is_empty());
+
+        // lengths that don't fit in the type itself
+        let s = IntervalSet::from_range(i64::MIN..=i64::MAX);
+        assert_eq!(s.min(), Some(i64::MIN));
+        assert_eq!(s.len(), Some(1 << 64));
+        assert_eq!(IntervalSet::from_range(i128::MIN..=i128::MAX).len(), None);
+        assert_eq!(IntervalSet::from_range(0..=u128::MAX).len(), None);
+        assert_eq!(IntervalSet::from_range(0..u128::MAX).len(), Some(u128::MAX));
+    }
+
+    #[test]
+    fn test_insert_merges() {
+        let mut s: IntervalSet<i32> = [0..2, 10..12, 20..22, 30..32].into_iter().collect();
+        s.insert(11..=20);
+        assert_eq!(s.iter().collect::<Vec<_>>(), vec![0..=1, 10..=21, 30..=31]);
+        s.insert(2..3);
+        s.insert(-5..-3);
+        s.insert(40..41);
+        assert_eq!(
+            s.iter().collect::<Vec<_>>(),
+            vec![-5..=-4, 0..=2, 10..=21, 30..=31, 40..=40]
+        );
+        s.insert(-10..100);
+        assert_eq!(s, IntervalSet::from_range(-10..100));
+    }
+
+    fn _naive(set: &IntervalSet<i32>) -> BTreeSet<i32> {
+        set.iter().flatten().collect()
+    }
+
+    fn _set() -> impl Strategy<Value = IntervalSet<i32>> {
+        prop::collection::vec((-50..50i32, 0..15i32, any::<bool>()), 0..6).prop_map(|ranges| {
+            let mut res = IntervalSet::new();
+            for (start, len, inclusive) in ranges {
+                match inclusive {
+                    true => res.insert(start..=start + len),
+                    false => res.insert(start..start + len),
+                }
+            }
+            res
+        })
+    }
+
+    /// small sets crowded up against `u8::MAX`
+    fn _set_u8() -> impl Strategy<Value = IntervalSet<u8>> {
+        prop::collection::vec((220..=u8::MAX, 0..15u8), 0..6).prop_map(|ranges| {
+            ranges
+                .into_iter()
+                .map(|(start, len)| start..=start.saturating_add(len))
+                .collect()
+        })
+    }
+
+    fn _naive_u8(set: &IntervalSet<u8>) -> BTreeSet<u8> {
+        set.iter().flatten().collect()
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_naive_at_max(a in _set_u8(), b in _set_u8(), at in 200..=u8::MAX) {
+            let (na, nb) = (_naive_u8(&a), _naive_u8(&b));
+
+            prop_assert_eq!(_naive_u8(&a.union(&b)), &na | &nb);
+            prop_assert_eq!(_naive_u8(&a.intersection(&b)), &na & &nb);
+            prop_assert_eq!(_naive_u8(&a.difference(&b)), &na - &nb);
+            prop_assert_eq!(a.len(), Some(na.len() as u128));
+            prop_assert_eq!(a.contains(at), na.contains(&at));
+
+            let (lo, hi) = a.split_at(at);
+            prop_assert_eq!(_naive_u8(&lo), na.iter().copied().filter(|v| *v < at).collect());
+            prop_assert_eq!(_naive_u8(&hi), na.iter().copied().filter(|v| *v >= at).collect());
+        }
+
+
+        #[test]
+        fn prop_matches_naive(a in _set(), b in _set(), at in -60..60i32) {
+            let (na, nb) = (_naive(&a), _naive(&b));
+
+            prop_assert_eq!(_naive(&a.union(&b)), &na | &nb);
+            prop_assert_eq!(_naive(&a.intersection(&b)), &na & &nb);
+            prop_assert_eq!(_naive(&a.difference(&b)), &na - &nb);
+            prop_assert_eq!(a.len(), Some(na.len() as u128));
+            prop_assert_eq!(a.contains(at), na.contains(&at));
+            prop_assert_eq!(a.is_superset(&b), nb.is_subset(&na));
+
+            let (lo, hi) = a.split_at(at);
+            prop_assert!(_naive(&lo).iter().all(|v| *v < at));
+            prop_assert!(_naive(&hi).iter().all(|v| *v >= at));
+            prop_assert_eq!(&_naive(&lo) | &_naive(&hi), na);
+        }
+
+        #[test]
+        fn prop_coalesced(a in _set()) {
+            let intervals = a.iter().collect::<Vec<_>>();
+            prop_assert!(intervals.iter().all(|r| r.start() <= r.end()));
+            prop_assert!(intervals.windows(2).all(|w| w[0].end() + 1 < *w[1].start()));
+        }
+    }
+}
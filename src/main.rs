@@ -3,6 +3,16 @@
 
 pub mod utils;
 pub mod point;
+pub mod grid;
+pub mod compress;
+pub mod interval;
+pub mod math;
+pub mod search;
+pub mod flood;
+pub mod cycle;
+pub mod graph;
+pub mod dot;
+pub mod vm;
 // pub mod aoc2022;
 pub mod aoc2020;
 pub mod aoc2023;
@@ -57,6 +67,7 @@ fn run_aoc2023() {
     run!(day10);
     run!(day11);
     run!(day11_faster);
+    run!(day12);
     run!(day13);
     run!(day14);
     run!(day15);
@@ -0,0 +1,284 @@
+use std::ops::RangeInclusive;
+
+use num_integer::{Integer, Roots};
+
+/// finite-difference table of values sampled at `x = 0, 1, 2, ...`
+///
+/// row 0 is the values, each row after is the differences between neighbors in the one
+/// above. building stops at the first all-zero row, which means the values are a polynomial
+/// and every row needed to extend them is here
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Differences {
+    rows: Vec<Vec<i128>>,
+}
+
+impl Differences {
+    pub fn new(values: impl IntoIterator<Item = impl Into<i128>>) -> Self {
+        let mut rows = vec![values.into_iter().map(Into::into).collect::<Vec<i128>>()];
+        loop {
+            let last = rows.last().unwrap();
+            if last.len() < 2 || last.iter().all(|v| *v == 0) {
+                break;
+            }
+            let diffs = last.windows(2).map(|w| w[1] - w[0]).collect();
+            rows.push(diffs);
+        }
+        Self { rows }
+    }
+
+    pub fn rows(&self) -> &[Vec<i128>] {
+        &self.rows
+    }
+
+    /// degree of the polynomial through the values, or `None` if there weren't enough of
+    /// them to reach a zero row. all zeros counts as degree 0
+    pub fn degree(&self) -> Option<usize> {
+        let last = self.rows.last()?;
+        if last.is_empty() || last.iter().any(|v| *v != 0) {
+            return None;
+        }
+        Some(self.rows.len().saturating_sub(2))
+    }
+
+    /// value at `x`, which can be anywhere: before 0, past the end, or in between
+    ///
+    /// newton's forward formula, `f(x) = sum(C(x, k) * first difference in row k)`, with `C`
+    /// extended to negative `x`. if the table never reached a zero row, this is the lowest
+    /// degree polynomial through the values
+    pub fn extrapolate(&self, x: i128) -> i128 {
+        let mut res = 0;
+        // C(x, k), updated in place. each step divides exactly
+        let mut choose = 1;
+        for (k, row) in self.rows.iter().enumerate() {
+            let Some(first) = row.first() else {
+                break;
+            };
+            res += choose * first;
+            choose = choose * (x - k as i128) / (k as i128 + 1);
+        }
+        res
+    }
+
+    /// the value after the last one
+    pub fn next(&self) -> i128 {
+        self.extrapolate(self.rows[0].len() as i128)
+    }
+
+    /// the value before the first one
+    pub fn prev(&self) -> i128 {
+        self.extrapolate(-1)
+    }
+}
+
+/// value at `x` of the lowest degree polynomial through `points`
+///
+/// kept as an exact fraction the whole way through. `None` if two points share an `x` or
+/// the answer isn't an integer
+pub fn lagrange(points: &[(i128, i128)], x: i128) -> Option<i128> {
+    let (mut num, mut den) = (0, 1);
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        let (mut term_num, mut term_den) = (*yi, 1);
+        for (j, (xj, _)) in points.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            if xi == xj {
+                return None;
+            }
+            (term_num, term_den) = _reduce(term_num * (x - xj), term_den * (xi - xj));
+        }
+        (num, den) = _reduce(num * term_den + term_num * den, den * term_den);
+    }
+    (num % den == 0).then(|| num / den)
+}
+
+/// lowest terms, with a positive denominator
+fn _reduce(num: i128, den: i128) -> (i128, i128) {
+    let g = num.gcd(&den) * den.signum();
+    (num / g, den / g)
+}
+
+pub fn gcd_all<T: Integer>(values: impl IntoIterator<Item = T>) -> T {
+    values.into_iter().fold(T::zero(), |acc, v| acc.gcd(&v))
+}
+
+/// 1 for no values, like an empty product
+pub fn lcm_all<T: Integer>(values: impl IntoIterator<Item = T>) -> T {
+    values.into_iter().fold(T::one(), |acc, v| acc.lcm(&v))
+}
+
+/// the `x` satisfying every `x ≡ residue (mod modulus)`, as `(x, modulus)` with `x` the
+/// smallest non-negative one and `modulus` the lcm of them all
+///
+/// moduli don't need to be coprime. `None` if the congruences contradict each other, a
+/// modulus is 0, or the combined modulus doesn't fit in a `u64`
+pub fn crt(congruences: impl IntoIterator<Item = (u64, u64)>) -> Option<(u64, u64)> {
+    let (res, modulus) =
+        congruences
+            .into_iter()
+            .try_fold((0i128, 1i128), |(r1, m1), (r2, m2)| {
+                if m2 == 0 {
+                    return None;
+                }
+                let (r2, m2) = (i128::from(r2), i128::from(m2));
+                let egcd = m1.extended_gcd(&m2);
+                let g = egcd.gcd;
+                if (r2 - r1) % g != 0 {
+                    return None;
+                }
+                let modulus = (m1 / g).checked_mul(m2)?;
+                let k = ((r2 - r1) / g).checked_mul(egcd.x)?.rem_euclid(m2 / g);
+                Some(((r1 + m1.checked_mul(k)?).rem_euclid(modulus), modulus))
+            })?;
+    Some((u64::try_from(res).ok()?, u64::try_from(modulus).ok()?))
+}
+
+/// `x` in `0..modulus` with `a * x ≡ 1`, if `a` and `modulus` are coprime
+pub fn mod_inv(a: u64, modulus: u64) -> Option<u64> {
+    let egcd = (a as i128).extended_gcd(&(modulus as i128));
+    (egcd.gcd == 1).then(|| egcd.x.rem_euclid(modulus as i128) as u64)
+}
+
+/// `base ^ exp % modulus` by repeated squaring
+pub fn mod_pow(base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let m = modulus as u128;
+    let (mut base, mut res) = (base as u128 % m, 1 % m);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = res * base % m;
+        }
+        base = base * base % m;
+        exp >>= 1;
+    }
+    res as u64
+}
+
+/// every integer `x` with `a*x^2 + b*x + c < 0`, which is a single run since `a` must be
+/// positive. `None` if there aren't any
+///
+/// starts from the integer square root of the discriminant, then nudges each end until it's
+/// exact, so there's no float rounding to worry about
+pub fn quadratic_below_zero(a: i64, b: i64, c: i64) -> Option<RangeInclusive<i64>> {
+    assert!(a > 0, "parabola has to open upwards");
+    let (a, b, c) = (a as i128, b as i128, c as i128);
+    let f = |x: i128| a * x * x + b * x + c;
+    let disc = b * b - 4 * a * c;
+    if disc <= 0 {
+        return None;
+    }
+    let root = disc.sqrt();
+    let (mut lo, mut hi) = (
+        Integer::div_floor(&(-b - root), &(2 * a)),
+        Integer::div_ceil(&(-b + root), &(2 * a)),
+    );
+    while f(lo) >= 0 && lo <= hi {
+        lo += 1;
+    }
+    while f(lo - 1) < 0 {
+        lo -= 1;
+    }
+    while f(hi) >= 0 && hi >= lo {
+        hi -= 1;
+    }
+    while f(hi + 1) < 0 {
+        hi += 1;
+    }
+    (lo <= hi).then_some(lo as i64..=hi as i64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_differences() {
+        let d = Differences::new([1, 3, 6, 10, 15, 21]);
+        assert_eq!(d.degree(), Some(2));
+        assert_eq!(d.rows().len(), 4);
+        assert_eq!((d.next(), d.prev()), (28, 0));
+        assert_eq!(d.extrapolate(-3), 1);
+        assert_eq!(d.extrapolate(99), 5050);
+
+        assert_eq!(Differences::new([7, 7, 7]).degree(), Some(0));
+        assert_eq!(Differences::new([0, 0]).degree(), Some(0));
+        assert_eq!(Differences::new([1, 2, 4]).degree(), None);
+        assert_eq!(Differences::new(Vec::<i32>::new()).degree(), None);
+        // not enough values to be sure, so use the lowest degree that fits
+        assert_eq!(Differences::new([1, 2, 4]).next(), 7);
+    }
+
+    #[test]
+    fn test_lagrange() {
+        let cube = |x: i128| x * x * x - 4 * x + 2;
+        let points = [-2, 1, 3, 10].map(|x| (x, cube(x)));
+        for x in -20..20 {
+            assert_eq!(lagrange(&points, x), Some(cube(x)));
+        }
+        // matches the difference table on evenly spaced samples
+        let d = Differences::new((0..4).map(cube));
+        assert_eq!(lagrange(&points, 1000), Some(d.extrapolate(1000)));
+
+        // the line through these is y = x / 2
+        assert_eq!(lagrange(&[(0, 0), (2, 1)], 3), None);
+        assert_eq!(lagrange(&[(0, 0), (2, 1)], 4), Some(2));
+        assert_eq!(lagrange(&[(1, 0), (1, 1)], 4), None);
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd_all([12u64, 18, 30]), 6);
+        assert_eq!(lcm_all([4u64, 6, 10]), 60);
+        assert_eq!(lcm_all(Vec::<u64>::new()), 1);
+        assert_eq!(gcd_all(Vec::<u64>::new()), 0);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        // not coprime
+        assert_eq!(crt([(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt([(1, 4), (2, 6)]), None);
+        assert_eq!(crt([]), Some((0, 1)));
+        assert_eq!(crt([(1, 0)]), None);
+
+        // 2^61 ≡ 1 (mod 2^61 - 1) and 2^61 ≡ 2 (mod 7)
+        let p = (1 << 61) - 1;
+        assert_eq!(crt([(1, p), (2, 7)]), Some((1 << 61, 7 * p)));
+        // combined modulus too big for a u64, then too big for the working i128s
+        assert_eq!(crt([(1, p), (2, p - 1)]), None);
+        assert_eq!(
+            crt([(1, u64::MAX), (2, u64::MAX - 1), (3, u64::MAX - 2)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_modular() {
+        assert_eq!(mod_inv(3, 11), Some(4));
+        assert_eq!(mod_inv(4, 6), None);
+        assert_eq!(mod_pow(3, 200, 13), 9);
+        assert_eq!(mod_pow(5, 0, 1), 0);
+        let big = (1 << 61) - 1;
+        assert_eq!(mod_pow(2, 61, big), 1);
+        assert_eq!(mod_pow(7, big - 1, big), 1);
+    }
+
+    #[test]
+    fn test_quadratic_below_zero() {
+        let naive = |a: i64, b: i64, c: i64| {
+            let xs = (-100..100)
+                .filter(|x| a * x * x + b * x + c < 0)
+                .collect::<Vec<_>>();
+            xs.first().map(|lo| *lo..=*xs.last().unwrap())
+        };
+        for a in 1..4 {
+            for b in -30..30 {
+                for c in -30..30 {
+                    assert_eq!(quadratic_below_zero(a, b, c), naive(a, b, c), "{a} {b} {c}");
+                }
+            }
+        }
+        // roots land exactly on integers, which are excluded
+        assert_eq!(quadratic_below_zero(1, -30, 200), Some(11..=19));
+    }
+}
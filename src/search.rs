@@ -0,0 +1,219 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+};
+
+/// a puzzle's state space: implement this and get bfs/dijkstra/a* for free
+pub trait StateSpace {
+    type State: Clone + Eq + Hash;
+
+    /// neighboring states along with the cost of moving to them
+    fn successors(&self, state: &Self::State) -> Vec<(Self::State, u32)>;
+
+    /// search stops as soon as a goal state is settled. default: search everything
+    fn is_goal(&self, _state: &Self::State) -> bool {
+        false
+    }
+
+    /// lower bound on the remaining cost to a goal. only used by `astar`
+    /// must never overestimate or the result isn't optimal
+    fn heuristic(&self, _state: &Self::State) -> u32 {
+        0
+    }
+}
+
+#[derive(Debug)]
+pub struct SearchResult<S> {
+    /// best known cost to every state reached
+    pub dist: HashMap<S, u32>,
+    /// first goal state settled, if any
+    pub goal: Option<S>,
+    /// number of states popped and expanded
+    pub visited: usize,
+    parents: HashMap<S, S>,
+}
+
+impl<S: Clone + Eq + Hash> SearchResult<S> {
+    pub fn goal_cost(&self) -> Option<u32> {
+        self.goal.as_ref().map(|g| self.dist[g])
+    }
+
+    /// path from a start state to `to`, inclusive of both ends
+    pub fn path(&self, to: &S) -> Option<Vec<S>> {
+        if !self.dist.contains_key(to) {
+            return None;
+        }
+        let mut res = vec![to.clone()];
+        while let Some(parent) = self.parents.get(res.last().unwrap()) {
+            res.push(parent.clone());
+        }
+        res.reverse();
+        Some(res)
+    }
+
+    fn new() -> Self {
+        Self {
+            dist: HashMap::new(),
+            goal: None,
+            visited: 0,
+            parents: HashMap::new(),
+        }
+    }
+}
+
+/// breadth first search. every edge costs 1 regardless of what `successors` says
+pub fn bfs<SS: StateSpace>(
+    space: &SS,
+    starts: impl IntoIterator<Item = SS::State>,
+) -> SearchResult<SS::State> {
+    bfs_within(space, starts, u32::MAX)
+}
+
+/// `bfs`, but don't go further than `max_depth` steps from a start
+pub fn bfs_within<SS: StateSpace>(
+    space: &SS,
+    starts: impl IntoIterator<Item = SS::State>,
+    max_depth: u32,
+) -> SearchResult<SS::State> {
+    let mut res = SearchResult::new();
+    let mut queue = VecDeque::new();
+    for s in starts {
+        if !res.dist.contains_key(&s) {
+            res.dist.insert(s.clone(), 0);
+            queue.push_back(s);
+        }
+    }
+
+    while let Some(state) = queue.pop_front() {
+        res.visited += 1;
+        if space.is_goal(&state) {
+            res.goal = Some(state);
+            break;
+        }
+        let depth = res.dist[&state];
+        if depth >= max_depth {
+            continue;
+        }
+        for (next, _) in space.successors(&state) {
+            if res.dist.contains_key(&next) {
+                continue;
+            }
+            res.dist.insert(next.clone(), depth + 1);
+            res.parents.insert(next.clone(), state.clone());
+            queue.push_back(next);
+        }
+    }
+    res
+}
+
+/// lowest cost search using a priority queue
+pub fn dijkstra<SS: StateSpace>(
+    space: &SS,
+    starts: impl IntoIterator<Item = SS::State>,
+) -> SearchResult<SS::State> {
+    _best_first(space, starts, |_| 0)
+}
+
+/// `dijkstra` guided by `StateSpace::heuristic`
+pub fn astar<SS: StateSpace>(
+    space: &SS,
+    starts: impl IntoIterator<Item = SS::State>,
+) -> SearchResult<SS::State> {
+    _best_first(space, starts, |s| space.heuristic(s))
+}
+
+/// states aren't required to be `Ord`, so the heap holds indexes into `states`
+fn _best_first<SS: StateSpace>(
+    space: &SS,
+    starts: impl IntoIterator<Item = SS::State>,
+    heuristic: impl Fn(&SS::State) -> u32,
+) -> SearchResult<SS::State> {
+    let mut res = SearchResult::new();
+    let mut states = vec![];
+    let mut heap = BinaryHeap::new();
+
+    for s in starts {
+        res.dist.insert(s.clone(), 0);
+        heap.push(Reverse((heuristic(&s), 0, states.len())));
+        states.push(s);
+    }
+
+    while let Some(Reverse((_, cost, idx))) = heap.pop() {
+        let state = states[idx].clone();
+        // stale entry, a cheaper way here was already found
+        if res.dist[&state] < cost {
+            continue;
+        }
+        res.visited += 1;
+        if space.is_goal(&state) {
+            res.goal = Some(state);
+            break;
+        }
+        for (next, step_cost) in space.successors(&state) {
+            let next_cost = cost + step_cost;
+            if res.dist.get(&next).is_some_and(|d| *d <= next_cost) {
+                continue;
+            }
+            res.dist.insert(next.clone(), next_cost);
+            res.parents.insert(next.clone(), state.clone());
+            heap.push(Reverse((
+                next_cost + heuristic(&next),
+                next_cost,
+                states.len(),
+            )));
+            states.push(next);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// number line where you can step +1 for 1 or +5 for 3, heading for `target`
+    struct Line {
+        target: i32,
+    }
+
+    impl StateSpace for Line {
+        type State = i32;
+
+        fn successors(&self, state: &i32) -> Vec<(i32, u32)> {
+            [(state + 1, 1), (state + 5, 3)]
+                .into_iter()
+                .filter(|(s, _)| *s <= self.target)
+                .collect()
+        }
+
+        fn is_goal(&self, state: &i32) -> bool {
+            *state == self.target
+        }
+
+        fn heuristic(&self, state: &i32) -> u32 {
+            ((self.target - state) * 3 / 5) as u32
+        }
+    }
+
+    #[test]
+    fn test_bfs() {
+        let res = bfs(&Line { target: 12 }, [0]);
+        // 5, 10, 11, 12
+        assert_eq!(res.goal_cost(), Some(4));
+        assert_eq!(res.path(&12).unwrap().len(), 5);
+        assert_eq!(bfs_within(&Line { target: 12 }, [0], 2).goal, None);
+    }
+
+    #[test]
+    fn test_dijkstra_astar() {
+        let line = Line { target: 12 };
+        let d = dijkstra(&line, [0]);
+        let a = astar(&line, [0]);
+        assert_eq!(d.goal_cost(), Some(8));
+        assert_eq!(a.goal_cost(), Some(8));
+        let path = a.path(&12).unwrap();
+        assert_eq!((path[0], path[path.len() - 1]), (0, 12));
+        assert!(a.visited <= d.visited);
+    }
+}
@@ -1,6 +1,10 @@
-use std::fs;
+use std::{collections::HashMap, fs, hash::Hash, path::PathBuf};
 
 const BASE_PATH: &str = "/Users/acushner/software/rust/rsaoc/input";
+/// where generated graphviz files go
+const DOT_PATH: &str = "/Users/acushner/software/rust/rsaoc/dot";
+/// where recorded simulation traces go
+const TRACE_PATH: &str = "/Users/acushner/software/rust/rsaoc/trace";
 
 #[macro_export]
 macro_rules! print1 {
@@ -25,6 +29,14 @@ pub fn read_file(path: &str, year: u16) -> Vec<String> {
     let contents = fs::read_to_string(_full_path(path, year)).expect("file read");
     contents.lines().map(String::from).collect()
 }
+/// like `read_file`, but for days whose input might not be checked in
+pub fn try_read_file(path: &str, year: u16) -> Result<Vec<String>, String> {
+    let full_path = _full_path(path, year);
+    let contents =
+        fs::read_to_string(&full_path).map_err(|e| format!("can't read {full_path}: {e}"))?;
+    Ok(contents.lines().map(String::from).collect())
+}
+
 pub fn read_file20(path: &str) -> Vec<String> {
     read_file(path, 2020)
 }
@@ -49,3 +61,126 @@ pub fn to_int_vec(group: &[String]) -> Vec<i32> {
 fn _full_path(suffix: &str, year: u16) -> String {
     format!("{}/{}/{}", BASE_PATH, year, suffix)
 }
+
+pub fn dot_path(fname: &str, year: u16) -> PathBuf {
+    PathBuf::from(format!("{}/{}/{}", DOT_PATH, year, fname))
+}
+
+pub fn trace_path(fname: &str, year: u16) -> PathBuf {
+    PathBuf::from(format!("{}/{}/{}", TRACE_PATH, year, fname))
+}
+
+/// cache for recursive functions, owned by the caller instead of living in a global static
+///
+/// keys can borrow from the puzzle input, so make a fresh one (or `clear`) per input.
+/// recurse by calling back into the `Memo` handed to the closure:
+/// ```ignore
+/// fn count(memo: &mut Memo<&str, u64>, graph: &Graph, node: &str) -> u64 {
+///     memo.get_or_insert_with(node, |memo| {
+///         graph[node].iter().map(|n| count(memo, graph, n)).sum()
+///     })
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// cached value for `key`, computing it with `f` if missing
+    pub fn get_or_insert_with(&mut self, key: K, f: impl FnOnce(&mut Self) -> V) -> V {
+        if let Some(v) = self.cache.get(&key) {
+            self.hits += 1;
+            return v.clone();
+        }
+        self.misses += 1;
+        let v = f(self);
+        self.cache.insert(key, v.clone());
+        v
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.cache.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// drop all cached values and reset the stats
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fib(memo: &mut Memo<u64, u64>, n: u64) -> u64 {
+        if n < 2 {
+            return n;
+        }
+        memo.get_or_insert_with(n, |memo| fib(memo, n - 1) + fib(memo, n - 2))
+    }
+
+    #[test]
+    fn test_memo() {
+        let mut memo = Memo::new();
+        assert_eq!(fib(&mut memo, 90), 2880067194370816120);
+        assert_eq!(memo.misses(), 89);
+        assert_eq!(memo.hits(), 87);
+        assert!(memo.hit_rate() > 0.49);
+
+        memo.clear();
+        assert!(memo.is_empty());
+        assert_eq!(memo.hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_memo_borrowed_keys() {
+        let words = ["a".to_string(), "ab".to_string()];
+        let mut memo: Memo<&str, usize> = Memo::new();
+        for w in &words {
+            memo.get_or_insert_with(w.as_str(), |_| w.len());
+        }
+        assert_eq!(memo.get(&"ab"), Some(&2));
+    }
+}
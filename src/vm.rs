@@ -0,0 +1,537 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+pub mod handheld;
+
+/// a register machine with a debugger, generic over its instruction set
+///
+/// runs step by step or until something interesting happens: termination, a breakpoint, a
+/// caller-supplied condition, or (with `detect_loops`) a state about to repeat, so it will
+/// loop forever.
+///
+/// if no instruction's jump depends on the registers, the `pc` alone is the state. otherwise
+/// the registers are part of it too
+#[derive(Debug, Clone)]
+pub struct Machine<S: InstructionSet> {
+    pub program: Vec<S::Instruction>,
+    pub registers: Registers,
+    pub pc: usize,
+    breakpoints: HashSet<usize>,
+    /// `None` until `detect_loops` is called
+    loops: Option<LoopDetector>,
+    /// control flow doesn't depend on the registers
+    static_flow: bool,
+    trace: Option<Vec<TraceEntry<S::Instruction>>>,
+}
+
+/// everything executed so far, for spotting a state that's about to repeat
+#[derive(Debug, Clone, Default)]
+struct LoopDetector {
+    /// every address executed so far, in order
+    history: Vec<usize>,
+    /// state -> index of its first appearance in `history`
+    seen: HashMap<(usize, Vec<i64>), usize>,
+}
+
+/// a family of instructions along with the registers they work on
+///
+/// to add one: define its instructions, implement `Execute` for them and register a parser
+/// for each mnemonic
+pub trait InstructionSet {
+    type Instruction: Execute;
+
+    fn registers() -> &'static [&'static str];
+
+    fn register_parsers(parser: &mut Parser<Self::Instruction>);
+
+    fn parser() -> Parser<Self::Instruction> {
+        let mut parser = Parser::new(Self::registers());
+        Self::register_parsers(&mut parser);
+        parser
+    }
+}
+
+pub trait Execute: Copy + Debug + Display {
+    /// run against `registers`, returning how far to move the `pc`
+    fn execute(&self, registers: &mut Registers) -> i64;
+
+    /// how far this moves the `pc` if that never depends on the registers
+    fn static_offset(&self) -> Option<i64>;
+
+    /// the swapped version of this instruction for `Machine::find_patch`, if it has one
+    fn patched(&self) -> Option<Self> {
+        None
+    }
+}
+
+/// builds instructions from `mnemonic arg arg...` lines
+pub struct Parser<I> {
+    registers: &'static [&'static str],
+    ops: HashMap<&'static str, ParseFn<I>>,
+}
+
+pub type ParseFn<I> = fn(args: &[&str], registers: &Registers) -> Result<I, String>;
+
+/// named integer registers, all starting at 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registers {
+    names: &'static [&'static str],
+    values: Vec<i64>,
+}
+
+/// an instruction argument that's either a register or a literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(usize),
+    Value(i64),
+}
+
+/// why a run stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Halt {
+    /// `pc` is just past the last instruction
+    Terminated,
+    /// a jump from `pc` would land outside the program. the instruction has already run
+    OutOfBounds(i64),
+    /// about to execute an instruction with a breakpoint on it
+    Breakpoint(usize),
+    /// the `run_until` condition matched
+    Condition,
+    /// about to repeat a state. only with `detect_loops` on
+    Loop(Loop),
+}
+
+/// the addresses that repeat forever, starting at `entry`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub entry: usize,
+    pub addresses: Vec<usize>,
+}
+
+/// an executed instruction and the registers after it ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry<I> {
+    pub pc: usize,
+    pub instruction: I,
+    pub registers: Vec<i64>,
+}
+
+impl<S: InstructionSet> Machine<S> {
+    pub fn new(program: Vec<S::Instruction>) -> Self {
+        let static_flow = program.iter().all(|i| i.static_offset().is_some());
+        Self {
+            program,
+            registers: Registers::new(S::registers()),
+            pc: 0,
+            breakpoints: HashSet::new(),
+            loops: None,
+            static_flow,
+            trace: None,
+        }
+    }
+
+    /// back to the start, keeping the program, breakpoints and whether tracing and loop
+    /// detection are on
+    pub fn reset(&mut self) {
+        self.registers.reset();
+        self.pc = 0;
+        if let Some(loops) = &mut self.loops {
+            *loops = LoopDetector::default();
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.pc == self.program.len()
+    }
+
+    /// execute the instruction at `pc`
+    pub fn step(&mut self) -> Result<(), Halt> {
+        if self.is_terminated() {
+            return Err(Halt::Terminated);
+        }
+        if let Some(loops) = &mut self.loops {
+            let state = _state(self.pc, &self.registers, self.static_flow);
+            loops.seen.entry(state).or_insert(loops.history.len());
+            loops.history.push(self.pc);
+        }
+
+        let inst = self.program[self.pc];
+        let next = self.pc as i64 + inst.execute(&mut self.registers);
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceEntry {
+                pc: self.pc,
+                instruction: inst,
+                registers: self.registers.values.clone(),
+            });
+        }
+        if next < 0 || next > self.program.len() as i64 {
+            return Err(Halt::OutOfBounds(next));
+        }
+        self.pc = next as usize;
+        Ok(())
+    }
+
+    /// step until `cond` matches, a breakpoint is hit, or the program terminates or loops.
+    /// stops *before* executing the instruction at `pc`
+    ///
+    /// a breakpoint at the starting `pc` is ignored so runs can resume from one
+    pub fn run_until(&mut self, mut cond: impl FnMut(&Self) -> bool) -> Halt {
+        let mut first = true;
+        loop {
+            if cond(self) {
+                return Halt::Condition;
+            }
+            if !first && self.breakpoints.contains(&self.pc) {
+                return Halt::Breakpoint(self.pc);
+            }
+            if let Some(loops) = &self.loops {
+                if let Some(start) =
+                    loops
+                        .seen
+                        .get(&_state(self.pc, &self.registers, self.static_flow))
+                {
+                    return Halt::Loop(Loop {
+                        entry: self.pc,
+                        addresses: loops.history[*start..].to_vec(),
+                    });
+                }
+            }
+            if let Err(halt) = self.step() {
+                return halt;
+            }
+            first = false;
+        }
+    }
+
+    pub fn run(&mut self) -> Halt {
+        self.run_until(|_| false)
+    }
+
+    pub fn add_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.remove(&addr);
+    }
+
+    /// remember every state from here on, so runs stop with `Halt::Loop` right before one
+    /// repeats. that's a map entry per step (registers included unless control flow is
+    /// static), so leave it off for long-running programs that are expected to finish
+    pub fn detect_loops(&mut self) {
+        self.loops.get_or_insert_with(LoopDetector::default);
+    }
+
+    /// start recording every executed instruction
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    pub fn trace(&self) -> &[TraceEntry<S::Instruction>] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    /// one instruction per line with its address. `>` marks `pc`, `*` marks breakpoints
+    pub fn disassemble(&self) -> String {
+        self.program
+            .iter()
+            .enumerate()
+            .map(|(addr, inst)| {
+                let pc = if addr == self.pc { '>' } else { ' ' };
+                let bp = if self.breakpoints.contains(&addr) {
+                    '*'
+                } else {
+                    ' '
+                };
+                format!("{pc}{bp}{addr:04}  {inst}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// the single instruction swap that lets the program terminate, if there is one
+    ///
+    /// works backwards from the end to find every address that terminates unpatched, then walks
+    /// the unpatched run from the start looking for a swap that jumps into that set.
+    /// only possible when control flow doesn't depend on the registers
+    pub fn find_patch(&self) -> Option<usize> {
+        if !self.static_flow {
+            return None;
+        }
+        let terminates = self._terminating_addresses();
+        let mut m = Self::new(self.program.clone());
+        m.detect_loops();
+        m.run();
+        m.loops?.history.into_iter().find(|addr| {
+            self.program[*addr]
+                .patched()
+                .and_then(|inst| inst.static_offset())
+                .is_some_and(|offset| {
+                    let next = *addr as i64 + offset;
+                    next >= 0 && terminates.contains(&(next as usize))
+                })
+        })
+    }
+
+    /// swap the instruction at `addr` for its patched version
+    pub fn patch(&mut self, addr: usize) -> Result<(), String> {
+        let inst = &mut self.program[addr];
+        *inst = inst
+            .patched()
+            .ok_or(format!("can't patch {inst} at {addr}"))?;
+        self.static_flow = self.program.iter().all(|i| i.static_offset().is_some());
+        Ok(())
+    }
+
+    /// addresses that reach the end of the program when run unpatched
+    fn _terminating_addresses(&self) -> HashSet<usize> {
+        let len = self.program.len();
+        let mut sources: Vec<Vec<usize>> = vec![vec![]; len + 1];
+        for (addr, inst) in self.program.iter().enumerate() {
+            let next = addr as i64 + inst.static_offset().unwrap();
+            if (0..=len as i64).contains(&next) {
+                sources[next as usize].push(addr);
+            }
+        }
+
+        let mut res = HashSet::from([len]);
+        let mut stack = vec![len];
+        while let Some(addr) = stack.pop() {
+            for s in &sources[addr] {
+                if res.insert(*s) {
+                    stack.push(*s);
+                }
+            }
+        }
+        res
+    }
+}
+
+/// what has to match for the machine to be back where it was
+fn _state(pc: usize, registers: &Registers, static_flow: bool) -> (usize, Vec<i64>) {
+    match static_flow {
+        true => (pc, vec![]),
+        false => (pc, registers.values.clone()),
+    }
+}
+
+impl<S: InstructionSet> FromStr for Machine<S> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(S::parser().parse_program(s)?))
+    }
+}
+
+impl<I> Parser<I> {
+    pub fn new(registers: &'static [&'static str]) -> Self {
+        Self {
+            registers,
+            ops: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, mnemonic: &'static str, parse: ParseFn<I>) -> &mut Self {
+        self.ops.insert(mnemonic, parse);
+        self
+    }
+
+    pub fn parse(&self, line: &str) -> Result<I, String> {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let (mnemonic, args) = fields.split_first().ok_or("empty instruction")?;
+        let parse = self
+            .ops
+            .get(mnemonic)
+            .ok_or(format!("unknown instruction: {mnemonic}"))?;
+        parse(args, &Registers::new(self.registers)).map_err(|e| format!("{line}: {e}"))
+    }
+
+    pub fn parse_program(&self, s: &str) -> Result<Vec<I>, String> {
+        s.lines().map(|line| self.parse(line)).collect()
+    }
+}
+
+impl Registers {
+    pub fn new(names: &'static [&'static str]) -> Self {
+        Self {
+            names,
+            values: vec![0; names.len()],
+        }
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| *n == name)
+    }
+
+    pub fn values(&self) -> &[i64] {
+        &self.values
+    }
+
+    pub fn reset(&mut self) {
+        self.values.fill(0);
+    }
+
+    /// a register name or an integer
+    pub fn operand(&self, s: &str) -> Result<Operand, String> {
+        match self.index_of(s) {
+            Some(r) => Ok(Operand::Register(r)),
+            None => s
+                .parse()
+                .map(Operand::Value)
+                .map_err(|_| format!("bad operand: {s}")),
+        }
+    }
+
+    pub fn value(&self, op: Operand) -> i64 {
+        match op {
+            Operand::Register(r) => self.values[r],
+            Operand::Value(v) => v,
+        }
+    }
+}
+
+impl Index<usize> for Registers {
+    type Output = i64;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.values[index]
+    }
+}
+
+impl IndexMut<usize> for Registers {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.values[index]
+    }
+}
+
+impl Index<&str> for Registers {
+    type Output = i64;
+
+    fn index(&self, name: &str) -> &Self::Output {
+        let r = self
+            .index_of(name)
+            .unwrap_or_else(|| panic!("no register {name}"));
+        &self.values[r]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// cut-down assembunny from 2016 day 12, where jumps depend on the registers
+    struct Bunny;
+
+    #[derive(Debug, Clone, Copy)]
+    enum BunnyOp {
+        Cpy(Operand, usize),
+        Inc(usize),
+        Dec(usize),
+        Jnz(Operand, Operand),
+    }
+
+    impl Execute for BunnyOp {
+        fn execute(&self, registers: &mut Registers) -> i64 {
+            match *self {
+                BunnyOp::Cpy(src, r) => registers[r] = registers.value(src),
+                BunnyOp::Inc(r) => registers[r] += 1,
+                BunnyOp::Dec(r) => registers[r] -= 1,
+                BunnyOp::Jnz(cond, offset) if registers.value(cond) != 0 => {
+                    return registers.value(offset)
+                }
+                BunnyOp::Jnz(..) => {}
+            }
+            1
+        }
+
+        fn static_offset(&self) -> Option<i64> {
+            match self {
+                BunnyOp::Jnz(..) => None,
+                _ => Some(1),
+            }
+        }
+    }
+
+    impl Display for BunnyOp {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{self:?}")
+        }
+    }
+
+    fn _reg(regs: &Registers, s: &str) -> Result<usize, String> {
+        regs.index_of(s).ok_or(format!("no register {s}"))
+    }
+
+    impl InstructionSet for Bunny {
+        type Instruction = BunnyOp;
+
+        fn registers() -> &'static [&'static str] {
+            &["a", "b", "c", "d"]
+        }
+
+        fn register_parsers(parser: &mut Parser<BunnyOp>) {
+            parser
+                .register("cpy", |args, regs| match args {
+                    [src, dst] => Ok(BunnyOp::Cpy(regs.operand(src)?, _reg(regs, dst)?)),
+                    _ => Err("cpy takes 2 args".to_string()),
+                })
+                .register("inc", |args, regs| Ok(BunnyOp::Inc(_reg(regs, args[0])?)))
+                .register("dec", |args, regs| Ok(BunnyOp::Dec(_reg(regs, args[0])?)))
+                .register("jnz", |args, regs| match args {
+                    [cond, offset] => Ok(BunnyOp::Jnz(regs.operand(cond)?, regs.operand(offset)?)),
+                    _ => Err("jnz takes 2 args".to_string()),
+                });
+        }
+    }
+
+    #[test]
+    fn test_custom_set() {
+        let mut m: Machine<Bunny> = "cpy 41 a\ninc a\ninc a\ndec a\njnz a 2\ndec a"
+            .parse()
+            .unwrap();
+        assert_eq!(m.run(), Halt::Terminated);
+        assert_eq!(m.registers["a"], 42);
+        assert_eq!(m.find_patch(), None);
+
+        // a loop that changes registers isn't a repeat, one that doesn't is
+        let mut m: Machine<Bunny> = "cpy 3 a\ndec a\njnz a -1\njnz 1 0".parse().unwrap();
+        m.enable_trace();
+        m.detect_loops();
+        assert_eq!(
+            m.run(),
+            Halt::Loop(Loop {
+                entry: 3,
+                addresses: vec![3]
+            })
+        );
+        assert_eq!(m.trace().len(), 8);
+
+        // long runs don't keep anything around unless asked
+        let mut m: Machine<Bunny> = "cpy 100000 a\ndec a\njnz a -1".parse().unwrap();
+        assert_eq!(m.run(), Halt::Terminated);
+        assert!(m.loops.is_none());
+
+        assert!("mul a b".parse::<Machine<Bunny>>().is_err());
+        assert!("cpy 1".parse::<Machine<Bunny>>().is_err());
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut regs = Registers::new(&["x", "y"]);
+        regs[1] = 7;
+        assert_eq!(regs["y"], 7);
+        assert_eq!(regs.operand("x"), Ok(Operand::Register(0)));
+        assert_eq!(regs.operand("-4"), Ok(Operand::Value(-4)));
+        assert_eq!(regs.value(Operand::Register(1)), 7);
+        assert!(regs.operand("z").is_err());
+        regs.reset();
+        assert_eq!(regs.values(), &[0, 0]);
+    }
+}
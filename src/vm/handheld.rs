@@ -0,0 +1,216 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::{Execute, InstructionSet, Parser, Registers};
+
+/// the handheld game console from 2020 day 8: one accumulator and three instructions
+#[derive(Debug, Clone, Copy)]
+pub struct Handheld;
+
+pub const ACC: usize = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub type_: InstructionType,
+    pub offset: i64,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum InstructionType {
+    Acc,
+    Nop,
+    Jmp,
+}
+
+impl InstructionSet for Handheld {
+    type Instruction = Instruction;
+
+    fn registers() -> &'static [&'static str] {
+        &["acc"]
+    }
+
+    fn register_parsers(parser: &mut Parser<Instruction>) {
+        parser
+            .register("acc", |args, _| _parse(InstructionType::Acc, args))
+            .register("nop", |args, _| _parse(InstructionType::Nop, args))
+            .register("jmp", |args, _| _parse(InstructionType::Jmp, args));
+    }
+}
+
+fn _parse(type_: InstructionType, args: &[&str]) -> Result<Instruction, String> {
+    match args {
+        [offset] => Ok(Instruction {
+            type_,
+            offset: offset
+                .parse()
+                .map_err(|_| format!("bad offset: {offset}"))?,
+        }),
+        _ => Err("expected a single offset".to_string()),
+    }
+}
+
+impl Execute for Instruction {
+    fn execute(&self, registers: &mut Registers) -> i64 {
+        if self.type_ == InstructionType::Acc {
+            registers[ACC] += self.offset;
+        }
+        self.static_offset().unwrap()
+    }
+
+    /// jumps never depend on `acc`
+    fn static_offset(&self) -> Option<i64> {
+        Some(match self.type_ {
+            InstructionType::Jmp => self.offset,
+            _ => 1,
+        })
+    }
+
+    /// `nop` <-> `jmp`. `acc` can't be patched
+    fn patched(&self) -> Option<Self> {
+        let type_ = match self.type_ {
+            InstructionType::Nop => InstructionType::Jmp,
+            InstructionType::Jmp => InstructionType::Nop,
+            InstructionType::Acc => return None,
+        };
+        Some(Self { type_, ..*self })
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Handheld::parser().parse(s)
+    }
+}
+
+impl FromStr for InstructionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nop" => Ok(Self::Nop),
+            "acc" => Ok(Self::Acc),
+            "jmp" => Ok(Self::Jmp),
+            s => Err(format!("unknown instruction: {s}")),
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self.type_ {
+            InstructionType::Acc => "acc",
+            InstructionType::Nop => "nop",
+            InstructionType::Jmp => "jmp",
+        };
+        write!(f, "{name} {:+}", self.offset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::vm::{Halt, Loop, Machine};
+
+    use super::*;
+
+    const PROGRAM: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    fn _machine(s: &str) -> Machine<Handheld> {
+        let mut m: Machine<Handheld> = s.parse().unwrap();
+        m.detect_loops();
+        m
+    }
+
+    #[test]
+    fn test_loop() {
+        let mut m = _machine(PROGRAM);
+        let halt = m.run();
+        assert_eq!(m.registers[ACC], 5);
+        assert_eq!(
+            halt,
+            Halt::Loop(Loop {
+                entry: 1,
+                addresses: vec![1, 2, 6, 7, 3, 4]
+            })
+        );
+    }
+
+    #[test]
+    fn test_clone() {
+        let m = _machine(PROGRAM);
+        let mut copy = m.clone();
+        copy.run();
+        assert_eq!((m.registers[ACC], copy.registers[ACC]), (0, 5));
+        assert!(format!("{m:?}").starts_with("Machine"));
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut m = _machine(PROGRAM);
+        m.add_breakpoint(3);
+        m.enable_trace();
+        assert_eq!(m.run(), Halt::Breakpoint(3));
+        assert_eq!(m.registers["acc"], 2);
+        assert_eq!(
+            m.trace().iter().map(|t| t.pc).collect::<Vec<_>>(),
+            vec![0, 1, 2, 6, 7]
+        );
+        // resuming steps past the breakpoint
+        assert!(matches!(m.run(), Halt::Loop(_)));
+
+        m.reset();
+        assert_eq!(m.run_until(|m| m.registers[ACC] > 1), Halt::Condition);
+        assert_eq!(m.pc, 7);
+        assert!(m.disassemble().contains("\n *0003  acc +3\n"));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let mut m = _machine(PROGRAM);
+        m.add_breakpoint(2);
+        m.step().unwrap();
+        let lines = m
+            .disassemble()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert_eq!(lines[0], "  0000  nop +0");
+        assert_eq!(lines[1], "> 0001  acc +1");
+        assert_eq!(lines[2], " *0002  jmp +4");
+    }
+
+    #[test]
+    fn test_patch() {
+        let mut m = _machine(PROGRAM);
+        assert_eq!(m.find_patch(), Some(7));
+        m.patch(7).unwrap();
+        assert_eq!(m.run(), Halt::Terminated);
+        assert_eq!(m.registers[ACC], 8);
+        assert!(m.patch(1).is_err());
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let mut m = _machine("nop +0\njmp -2");
+        assert_eq!(m.run(), Halt::OutOfBounds(-1));
+        assert_eq!(m.pc, 1);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!("acc".parse::<Instruction>().is_err());
+        assert!("mul +1".parse::<Instruction>().is_err());
+        assert_eq!(
+            "nop -0".parse::<Instruction>().unwrap().to_string(),
+            "nop +0"
+        );
+    }
+}